@@ -1,4 +1,32 @@
-from python_mg._lib_name import Lexicon, Continuation, SyntacticStructure
+from python_mg._lib_name import (
+    Lexicon,
+    Continuation,
+    SyntacticStructure,
+    MDLScore,
+    ExpectedUsage,
+    LexiconComparison,
+    Counterexample,
+    MCFG,
+    MCFGRule,
+    Automaton,
+    FeatureGraph,
+    FeatureNode,
+    FeatureEdge,
+    GrammarTemplate,
+    GrammarVariant,
+    LexiconUnion,
+    LexiconDiff,
+    EntryChange,
+    DerivationStep,
+    MemoryLoadProfile,
+    MGError,
+    GrammarSyntaxError,
+    LOTSyntaxError,
+    LOTTypeError,
+    PresuppositionError,
+    ExecutionLimitError,
+    TokenizationError,
+)
 from python_mg.syntax import to_tree
 
 SyntacticStructure.to_tree = to_tree
@@ -6,4 +34,28 @@ __all__ = [
     "Lexicon",
     "Continuation",
     "SyntacticStructure",
+    "MDLScore",
+    "ExpectedUsage",
+    "LexiconComparison",
+    "Counterexample",
+    "MCFG",
+    "MCFGRule",
+    "Automaton",
+    "FeatureGraph",
+    "FeatureNode",
+    "FeatureEdge",
+    "GrammarTemplate",
+    "GrammarVariant",
+    "LexiconUnion",
+    "LexiconDiff",
+    "EntryChange",
+    "DerivationStep",
+    "MemoryLoadProfile",
+    "MGError",
+    "GrammarSyntaxError",
+    "LOTSyntaxError",
+    "LOTTypeError",
+    "PresuppositionError",
+    "ExecutionLimitError",
+    "TokenizationError",
 ]
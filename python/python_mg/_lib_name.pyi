@@ -1,10 +1,26 @@
 import datetime
+import os
 from typing import Literal, Sequence
 import numpy as np
 import numpy.typing as npt
 
 from python_mg.syntax import ParseTree
 
+class MGError(ValueError): ...
+
+class GrammarSyntaxError(MGError):
+    line: int | None
+    column: int | None
+    entry: str | None
+    parser: Literal["syntax", "lot"]
+    file: str | None
+
+class LOTSyntaxError(MGError): ...
+class LOTTypeError(MGError): ...
+class PresuppositionError(MGError): ...
+class ExecutionLimitError(MGError): ...
+class TokenizationError(MGError): ...
+
 class MGNode:
     def is_trace(self) -> bool: ...
     def trace_id(self) -> int: ...
@@ -29,7 +45,12 @@ class SyntacticStructure:
     def latex(self) -> str: ...
     def to_tree(self) -> ParseTree: ...
     def max_memory_load(self) -> int: ...
+    def steps(self) -> list[DerivationStep]: ...
+    def memory_load_profile(self) -> MemoryLoadProfile: ...
     def tokens(self) -> npt.NDArray[np.uint]: ...
+    def to_json(self) -> str: ...
+    @staticmethod
+    def from_json(s: str, lexicon: Lexicon) -> SyntacticStructure: ...
     @property
     def meaning(self) -> list[Meaning] | None: ...
     def __to_tree_inner(
@@ -46,6 +67,132 @@ class Continuation:
     def is_word(self) -> bool: ...
     def is_multi_word(self) -> bool: ...
 
+class MDLScore:
+    grammar: float
+    corpus: float
+    total: float
+    per_item: list[float]
+
+class ExpectedUsage:
+    entries: dict[str, float]
+    operations: dict[str, float]
+    entropy: float
+    expected_length: float
+    coverage: float
+
+class LexiconComparison:
+    kl_divergence: float
+    reverse_kl_divergence: float
+    js_divergence: float
+    total_variation: float
+    shared: set[str]
+    only_self: set[str]
+    only_other: set[str]
+
+class MCFGRule:
+    lhs: str
+    rhs: list[str]
+    components: list[list[str]]
+    entries: list[str]
+
+class MCFG:
+    start: str
+    rules: list[MCFGRule]
+    def to_text(self) -> str: ...
+    def dimensions(self) -> dict[str, int]: ...
+
+class Automaton:
+    @property
+    def n_states(self) -> int: ...
+    def accepts(self, tokens: list[int] | npt.NDArray[np.uint]) -> bool: ...
+    def next_tokens(
+        self, prefix: list[int] | npt.NDArray[np.uint]
+    ) -> npt.NDArray[np.bool_]: ...
+    def to_dot(self) -> str: ...
+
+class FeatureNode:
+    name: str
+    is_licensee: bool
+
+class FeatureEdge:
+    kind: str
+    entries: list[str]
+
+class FeatureGraph:
+    def nodes(self) -> list[tuple[int, FeatureNode]]: ...
+    def edges(self) -> list[tuple[int, int, FeatureEdge]]: ...
+    def to_dot(self) -> str: ...
+
+class GrammarVariant:
+    @property
+    def parameters(self) -> dict[str, bool]: ...
+    @property
+    def vector(self) -> list[bool]: ...
+    @property
+    def lexicon(self) -> Lexicon: ...
+
+class GrammarTemplate:
+    def __init__(self, template: str, semantic: bool | None = None) -> None: ...
+    @property
+    def parameters(self) -> list[str]: ...
+    def grammar(self, parameters: dict[str, bool]) -> str: ...
+    def instantiate(self, parameters: dict[str, bool]) -> GrammarVariant: ...
+    def expand(
+        self, settings: list[dict[str, bool]] | None = None
+    ) -> list[GrammarVariant]: ...
+
+class LexiconUnion:
+    @property
+    def lexicon(self) -> Lexicon: ...
+    @property
+    def clashes(self) -> list[str]: ...
+    @property
+    def renamed(self) -> dict[str, str]: ...
+    @property
+    def homophones(self) -> list[tuple[str, str]]: ...
+    @property
+    def semantics_dropped(self) -> bool: ...
+
+class EntryChange:
+    before: str
+    after: str
+    removed_features: list[str]
+    added_features: list[str]
+    reordered: bool
+    semantics_before: str | None
+    semantics_after: str | None
+
+class DerivationStep:
+    operation: Literal[
+        "lexical_insertion", "merge_left", "merge_right", "head_move", "move"
+    ]
+    feature: str | None
+    lexeme: str
+    memory: list[str]
+
+class MemoryLoadProfile:
+    movers: list[int]
+    words: list[str]
+    tenure: list[int]
+    stack_size: list[int]
+    max_tenure: int
+    sum_tenure: int
+    max_stack_size: int
+
+class LexiconDiff:
+    added: dict[str, list[str]]
+    removed: dict[str, list[str]]
+    modified: dict[str, list[EntryChange]]
+    def is_empty(self) -> bool: ...
+
+class Counterexample:
+    @property
+    def string(self) -> str: ...
+    @property
+    def parses(self) -> list[SyntacticStructure]: ...
+    @property
+    def accepted_by(self) -> Lexicon: ...
+
 class GrammarIterator:
     def __iter__(self) -> GrammarIterator: ...
     def __next__(self) -> SyntacticStructure: ...
@@ -53,10 +200,137 @@ class GrammarIterator:
 class Lexicon:
     """A Minimalist Grammar Lexicon."""
 
-    def __init__(self, s: str) -> None: ...
+    def __init__(
+        self,
+        s: str,
+        semantic: bool | None = None,
+        weights: dict[str, float] | None = None,
+        tokens: dict[str, int] | None = None,
+        metadata: dict[str, dict[str, str]] | None = None,
+    ) -> None: ...
+    @staticmethod
+    def from_file(
+        path: str | os.PathLike[str], semantic: bool | None = None
+    ) -> Lexicon: ...
+    def metadata(self) -> dict[str, dict[str, str]]: ...
+    def to_json(self) -> str: ...
     @staticmethod
-    def random_lexicon(lemmas: list[str]) -> "Lexicon": ...
+    def from_json(s: str) -> Lexicon: ...
+    def fingerprint(self) -> str: ...
+    def __hash__(self) -> int: ...
+    def weights(self) -> dict[str, float] | None: ...
+    def expected_usage(
+        self,
+        category: str,
+        min_log_prob: float | None = None,
+        move_prob: float = 0.5,
+        max_steps: int | None = 64,
+        n_beams: int | None = None,
+        max_derivations: int | None = None,
+    ) -> ExpectedUsage: ...
+    def compare(
+        self,
+        other: Lexicon,
+        category: str,
+        min_log_prob: float | None = None,
+        move_prob: float = 0.5,
+        max_steps: int | None = 64,
+        n_beams: int | None = None,
+        max_strings: int | None = None,
+    ) -> LexiconComparison: ...
+    def equivalent_to(
+        self,
+        other: Lexicon,
+        category: str,
+        max_len: int,
+        min_log_prob: float | None = None,
+        move_prob: float = 0.5,
+        max_steps: int | None = 64,
+        n_beams: int | None = None,
+    ) -> Counterexample | None: ...
+    def to_mcfg(self, start_category: str) -> MCFG: ...
+    def feature_graph(self) -> FeatureGraph: ...
+    def union(
+        self,
+        other: Lexicon,
+        rename_conflicts: bool = False,
+        namespace: str | None = None,
+    ) -> LexiconUnion: ...
+    def diff(self, other: Lexicon) -> LexiconDiff: ...
+    def restrict(
+        self, words: list[str] | None = None, entries: list[str] | None = None
+    ) -> Lexicon: ...
+    def mutate(
+        self,
+        seed: int,
+        operations: list[str] | None = None,
+        max_attempts: int = 100,
+    ) -> tuple[Lexicon, str]: ...
+    def to_automaton(
+        self,
+        category: str,
+        max_len: int,
+        min_log_prob: float | None = None,
+        move_prob: float = 0.5,
+        max_steps: int | None = 64,
+        n_beams: int | None = None,
+    ) -> Automaton: ...
+    def includes(
+        self,
+        other: Lexicon,
+        category: str,
+        max_len: int,
+        min_log_prob: float | None = None,
+        move_prob: float = 0.5,
+        max_steps: int | None = 64,
+        n_beams: int | None = None,
+    ) -> Counterexample | None: ...
+    def fit_weights(
+        self,
+        corpus: list[str],
+        category: str,
+        iterations: int = 10,
+        smoothing: float = 0.0,
+        min_log_prob: float | None = None,
+        move_prob: float = 0.5,
+        max_steps: int | None = 64,
+        n_beams: int | None = None,
+    ) -> tuple["Lexicon", list[float]]: ...
+    @staticmethod
+    def random_lexicon(
+        lemmas: list[str],
+        seed: int | None = None,
+        n_categories: int = 3,
+        n_licensees: int = 1,
+        head_movement_prob: float = 0.0,
+        max_features: int = 4,
+        start_category: str = "0",
+        max_steps: int | None = 64,
+    ) -> "Lexicon": ...
     def mdl(self, n_phonemes: int) -> float: ...
+    def mdl_breakdown(self, n_phonemes: int) -> dict[str, float]: ...
+    def corpus_mdl(
+        self,
+        corpus: Sequence[str | SyntacticStructure],
+        category: str,
+        n_phonemes: int,
+        min_log_prob: float | None = None,
+        move_prob: float = 0.5,
+        max_steps: int | None = 64,
+        n_beams: int | None = None,
+    ) -> MDLScore: ...
+    @staticmethod
+    def induce(
+        strings: list[str],
+        category: str,
+        n_phonemes: int,
+        search_budget: int = 1000,
+        seed: int | None = None,
+        min_log_prob: float | None = None,
+        move_prob: float = 0.5,
+        max_steps: int | None = 64,
+        n_beams: int | None = None,
+    ) -> tuple["Lexicon", list[float]]: ...
     def is_semantic(self) -> bool: ...
     def continuations(
         self,
@@ -121,6 +395,16 @@ class Lexicon:
         max_steps: int | None = 64,
         n_beams: int | None = 256,
     ) -> npt.NDArray[np.bool]: ...
+    def ngram_model(
+        self,
+        category: str,
+        n: int,
+        min_log_prob: float | None = None,
+        move_prob: float = 0.5,
+        max_steps: int | None = 64,
+        n_beams: int | None = None,
+        max_strings: int | None = None,
+    ) -> npt.NDArray[np.float64]: ...
 
 class Actor:
     name: str
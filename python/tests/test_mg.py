@@ -1,8 +1,30 @@
 # ruff: disable[D103,D100,E501]
 
+import itertools
+import json
+import math
 import pickle
 
-from python_mg import Lexicon, Continuation
+import pytest
+import rustworkx as rx
+
+from python_mg import (
+    Lexicon,
+    MCFG,
+    FeatureEdge,
+    FeatureNode,
+    GrammarTemplate,
+    SyntacticStructure,
+    Continuation,
+    DerivationStep,
+    ExecutionLimitError,
+    GrammarSyntaxError,
+    MGError,
+    LOTSyntaxError,
+    LOTTypeError,
+    PresuppositionError,
+    TokenizationError,
+)
 from python_mg.semantics import Meaning, PossibleEvent, Scenario, Actor, Event
 from python_mg.syntax import Trace, Mover
 
@@ -17,6 +39,927 @@ def test_lexicon() -> None:
     )
 
 
+def test_grammar_syntax_errors() -> None:
+    with pytest.raises(GrammarSyntaxError) as e:
+        _ = Lexicon("a::b= a\nb::b=+ c")
+    assert e.value.line == 2
+    assert e.value.column == 4
+    assert e.value.entry == "b::b=+ c"
+    assert e.value.parser == "syntax"
+
+    with pytest.raises(GrammarSyntaxError) as e:
+        _ = Lexicon(
+            "John::d::a_John\nruns::=d v::lambda a x some_e(e, pe_run(e), AgentOf(x,e)"
+        )
+    assert e.value.line == 2
+    assert e.value.column == 13
+    assert e.value.parser == "lot"
+
+    # A syntax error in a semantic lexicon is still reported as a syntax error
+    with pytest.raises(GrammarSyntaxError) as e:
+        _ = Lexicon("John::d::a_John\nruns::=d::lambda a x pe_run(x)")
+    assert e.value.line == 2
+    assert e.value.parser == "syntax"
+
+    with pytest.raises(GrammarSyntaxError):
+        _ = Lexicon("John::d::a_John", semantic=False)
+
+    assert not Lexicon("a::b= a\nb::b", semantic=False).is_semantic()
+    assert Lexicon("John::d::a_John", semantic=True).is_semantic()
+
+
+def test_error_hierarchy() -> None:
+    for error in [
+        GrammarSyntaxError,
+        LOTSyntaxError,
+        LOTTypeError,
+        PresuppositionError,
+        ExecutionLimitError,
+        TokenizationError,
+    ]:
+        assert issubclass(error, MGError)
+    assert issubclass(MGError, ValueError)
+
+    with pytest.raises(LOTSyntaxError):
+        _ = Meaning("pa_nice(")
+
+    scenario = Scenario.from_str("<John; {A: John (runs)}>")
+    with pytest.raises(LOTTypeError):
+        _ = scenario.evaluate("lambda a x pa_nice(x)")
+
+    with pytest.raises(PresuppositionError):
+        _ = scenario.evaluate("iota(x, some_e(e, pe_sleeps(e), AgentOf(x, e)))")
+
+    with pytest.raises(ExecutionLimitError):
+        _ = scenario.evaluate("some_e(e, pe_runs(e), AgentOf(a_John, e))", max_steps=1)
+
+    lexicon = Lexicon("a::b= a\nb::b")
+    with pytest.raises(TokenizationError):
+        _ = lexicon.parse_tokens([4, 5], "a")
+
+    with pytest.raises(GrammarSyntaxError):
+        _ = next(lexicon.generate_grammar("a")).contains_lexical_entry("a::b=")
+
+
+def test_random_lexicon() -> None:
+    lemmas = ["a", "b", "c", "d", "e"]
+    x = Lexicon.random_lexicon(lemmas, seed=12)
+    assert x == Lexicon.random_lexicon(lemmas, seed=12)
+
+    for seed in range(20):
+        x = Lexicon.random_lexicon(
+            lemmas,
+            seed=seed,
+            n_categories=4,
+            n_licensees=2,
+            head_movement_prob=0.5,
+            max_features=3,
+            start_category="S",
+        )
+        assert next(x.generate_grammar("S"), None) is not None
+
+    for seed in range(5):
+        x = Lexicon.random_lexicon(lemmas, seed=seed, n_categories=200, max_features=2)
+        assert next(x.generate_grammar("0"), None) is not None
+
+
+def test_mdl() -> None:
+    lexicon = Lexicon("a::b= a\nb::b\nc::b")
+    breakdown = lexicon.mdl_breakdown(26)
+    assert set(breakdown) == {"a::b= a", "b::b", "c::b"}
+    assert abs(sum(breakdown.values()) - lexicon.mdl(26)) < 1e-9
+    assert breakdown["b::b"] == breakdown["c::b"]
+    assert breakdown["a::b= a"] > breakdown["b::b"]
+
+    semantic = Lexicon("John::d::a_John\nMary::d::a_Mary\n::=d v::lambda a x pe_run(x)")
+    breakdown = semantic.mdl_breakdown(26)
+    assert set(breakdown) == {"John::d::a_John", "Mary::d::a_Mary", "::=d v::lambda a x pe_run(x)"}
+    assert abs(sum(breakdown.values()) - semantic.mdl(26)) < 1e-9
+
+    score = lexicon.corpus_mdl(["a b", "a c"], "a", 26)
+    assert score.grammar == lexicon.mdl(26)
+    assert len(score.per_item) == 2
+    assert score.per_item[0] > 0
+    assert abs(score.total - score.grammar - score.corpus) < 1e-9
+
+    parse = lexicon.parse("a b", "a")[0]
+    assert lexicon.corpus_mdl([parse], "a", 26).per_item == score.per_item[:1]
+
+    assert lexicon.corpus_mdl(["b a"], "a", 26).corpus == float("inf")
+
+    other = Lexicon("a::b= a\nb::b\nd::b")
+    with pytest.raises(MGError):
+        other.corpus_mdl([parse], "a", 26)
+
+
+def test_induce() -> None:
+    strings = ["the dog runs", "the cat runs", "the dog sleeps", "the cat sleeps"]
+    lexicon, trajectory = Lexicon.induce(strings, "s", 26, search_budget=200, seed=0)
+    for s in strings:
+        assert len(lexicon.parse(s, "s")) > 0
+    assert len(trajectory) == 201
+    assert all(a >= b for a, b in zip(trajectory, trajectory[1:]))
+    assert trajectory[-1] <= trajectory[0]
+    assert abs(lexicon.corpus_mdl(strings, "s", 26).total - trajectory[-1]) < 1e-9
+
+    lexicon, _ = Lexicon.induce(["runs"], "s", 26, search_budget=50, seed=0)
+    assert lexicon == Lexicon("runs::s")
+
+
+def test_weights() -> None:
+    grammar = "John::d\nMary::d\nruns::=d v"
+    lexicon = Lexicon(grammar)
+    weighted = Lexicon("John::d @ 3\nMary::d\nruns::=d v")
+    assert lexicon.weights() is None
+    assert weighted.weights() == {"John::d": 0.75, "Mary::d": 0.25, "runs::=d v": 1.0}
+    assert weighted == Lexicon(grammar, weights={"John::d": 3})
+    assert weighted != lexicon
+
+    for s, ratio in [("John runs", 1.5), ("Mary runs", 0.5)]:
+        p = lexicon.parse(s, "v")[0].log_prob()
+        q = weighted.parse(s, "v")[0].log_prob()
+        assert abs(q - p - math.log(ratio)) < 1e-9
+
+    strings = {" ".join(s): p for s, p in weighted.generate_unique_strings("v")}
+    assert strings["John runs"] > strings["Mary runs"]
+
+    pruned = Lexicon("John::d\nMary::d @ 0\nruns::=d v")
+    assert [str(x) for x in pruned.generate_grammar("v")] == ["John runs"]
+
+    with pytest.raises(GrammarSyntaxError) as e:
+        _ = Lexicon("John::d @ heavy\nruns::=d v")
+    assert e.value.line == 1
+    with pytest.raises(MGError):
+        _ = Lexicon(grammar, weights={"Sue::d": 1.0})
+
+
+def test_weighted_search() -> None:
+    weighted = Lexicon("Mary::d\nJohn::d @ 9\nruns::=d v")
+    bound = math.log(0.3)
+    structures = weighted.generate_grammar("v", min_log_prob=bound)
+    assert [str(x) for x in structures] == ["John runs"]
+    strings = weighted.generate_unique_strings("v", max_strings=1)
+    assert [" ".join(s) for s, _ in strings] == ["John runs"]
+    assert weighted.continuations("", "v", min_log_prob=bound) == {Continuation("John")}
+    assert weighted.parse("Mary runs", "v", min_log_prob=bound) == []
+    assert len(weighted.parse("John runs", "v", min_log_prob=bound)) == 1
+
+    with pytest.raises(MGError):
+        _ = weighted.parse("John runs", "v", n_beams=2)
+    with pytest.raises(MGError):
+        _ = weighted.parse("John runs", "v", min_log_prob=bound, max_steps=None)
+
+    recursive = Lexicon("John::d @ 3\nMary::d\nruns::=d v\nand::=v =v v")
+    strings = recursive.generate_unique_strings("v", max_steps=8, max_strings=2)
+    assert {" ".join(s) for s, _ in strings} == {"John runs", "Mary runs"}
+    assert len(recursive.parse("John runs and Mary runs", "v", max_steps=32)) == 1
+    with pytest.raises(MGError):
+        _ = recursive.generate_unique_strings("v", max_steps=None, max_strings=2)
+
+
+def test_equal_weights() -> None:
+    grammar = "John::d\nMary::d\nruns::=d v\nsees::d= =d v"
+    lexicon = Lexicon(grammar)
+    weighted = Lexicon(grammar, weights={"John::d": 2, "Mary::d": 2})
+    assert weighted.weights() is not None
+    for s in ["John runs", "Mary sees John"]:
+        p = lexicon.parse(s, "v")[0].log_prob()
+        q = weighted.parse(s, "v")[0].log_prob()
+        assert abs(p - q) < 1e-9
+
+
+def test_fit_weights() -> None:
+    lexicon = Lexicon("John::d\nMary::d\nSue::d\nruns::=d v")
+    corpus = ["John runs", "John runs", "John runs", "Mary runs"]
+    fitted, log_likelihoods = lexicon.fit_weights(corpus, "v", iterations=5)
+    assert len(log_likelihoods) == 5
+    assert all(a <= b + 1e-9 for a, b in zip(log_likelihoods, log_likelihoods[1:]))
+
+    weights = fitted.weights()
+    assert weights is not None
+    assert abs(weights["John::d"] - 0.75) < 1e-9
+    assert abs(weights["Mary::d"] - 0.25) < 1e-9
+    assert "Sue::d" not in weights
+
+    smoothed, _ = lexicon.fit_weights(corpus, "v", smoothing=1.0)
+    weights = smoothed.weights()
+    assert weights is not None
+    assert weights["Sue::d"] > 0
+
+    with pytest.raises(MGError):
+        _ = lexicon.fit_weights(["runs John"], "v")
+
+
+def test_expected_usage() -> None:
+    lexicon = Lexicon("John::d\nMary::d\nruns::=d v\nlikes::d= =d v")
+    usage = lexicon.expected_usage("v")
+    assert abs(usage.coverage - 1.0) < 1e-9
+    assert abs(usage.entries["runs::=d v"] - 0.5) < 1e-9
+    assert abs(usage.entries["likes::d= =d v"] - 0.5) < 1e-9
+    assert abs(usage.entries["John::d"] + usage.entries["Mary::d"] - 1.5) < 1e-9
+    assert abs(usage.operations["merge"] - 1.5) < 1e-9
+    assert usage.operations["move"] == 0
+    assert abs(usage.expected_length - 2.5) < 1e-9
+    # Two strings with probability 1/4 and four with probability 1/8.
+    assert abs(usage.entropy - 2.5) < 1e-9
+
+    weighted = Lexicon("John::d @ 3\nMary::d\nruns::=d v\nlikes::d= =d v")
+    usage = weighted.expected_usage("v")
+    assert abs(usage.entries["John::d"] - 1.125) < 1e-9
+
+
+def test_compare() -> None:
+    lexicon = Lexicon("John::d\nMary::d\nruns::=d v")
+    same = lexicon.compare(lexicon, "v")
+    assert same.kl_divergence == 0
+    assert same.js_divergence == 0
+    assert same.total_variation == 0
+    assert same.shared == {"John runs", "Mary runs"}
+    assert same.only_self == same.only_other == set()
+
+    other = Lexicon("John::d\nSue::d\nruns::=d v")
+    comparison = lexicon.compare(other, "v")
+    assert comparison.kl_divergence == float("inf")
+    assert abs(comparison.js_divergence - 0.5) < 1e-9
+    assert abs(comparison.total_variation - 0.5) < 1e-9
+    assert comparison.shared == {"John runs"}
+    assert comparison.only_self == {"Mary runs"}
+    assert comparison.only_other == {"Sue runs"}
+
+    weighted = Lexicon("John::d @ 3\nMary::d\nruns::=d v")
+    comparison = lexicon.compare(weighted, "v")
+    assert abs(comparison.total_variation - 0.25) < 1e-9
+    assert 0 < comparison.kl_divergence < float("inf")
+
+    # An affixed word is a different string from a lemma with a hyphen.
+    affixed = Lexicon("s::=>V T\ndrink::V")
+    hyphenated = Lexicon("drink-s::T")
+    comparison = affixed.compare(hyphenated, "T")
+    assert comparison.shared == set()
+    assert comparison.only_self == comparison.only_other == {"drink-s"}
+    assert abs(comparison.total_variation - 1.0) < 1e-9
+
+
+def test_equivalence() -> None:
+    lexicon = Lexicon("John::d\nMary::d\nruns::=d v")
+    reordered = Lexicon("Mary::d\nJohn::d\nruns::=d v")
+    bigger = Lexicon("John::d\nMary::d\nSue::d\nruns::=d v")
+    assert lexicon.equivalent_to(reordered, "v", 3) is None
+
+    counterexample = lexicon.equivalent_to(bigger, "v", 3)
+    assert counterexample is not None
+    assert counterexample.string == "Sue runs"
+    assert counterexample.accepted_by == bigger
+    assert [str(x) for x in counterexample.parses] == ["Sue runs"]
+
+    assert bigger.includes(lexicon, "v", 3) is None
+    counterexample = lexicon.includes(bigger, "v", 3)
+    assert counterexample is not None
+    assert counterexample.string == "Sue runs"
+
+    longer = Lexicon("John::d\nMary::d\nruns::=d v\nand::=v =v v")
+    assert lexicon.equivalent_to(longer, "v", 2) is None
+    assert longer.includes(lexicon, "v", 5) is None
+    counterexample = lexicon.equivalent_to(longer, "v", 5)
+    assert counterexample is not None
+    assert len(counterexample.string.split(" ")) == 5
+
+
+def mcfg_language(mcfg: MCFG) -> set[str]:
+    """The strings of a finite MCFG, found by applying its rules until nothing new is found."""
+
+    def component(symbols: list[str], args: tuple[tuple[str, ...], ...]) -> str:
+        words = []
+        for s in symbols:
+            if s.startswith("x"):
+                i, j = s[1:].split("_")
+                words.append(args[int(i)][int(j)])
+            else:
+                words.append(s)
+        return " ".join(" ".join(words).split())
+
+    tuples: dict[str, set[tuple[str, ...]]] = {}
+    changed = True
+    while changed:
+        changed = False
+        for rule in mcfg.rules:
+            children = [sorted(tuples.get(x, set())) for x in rule.rhs]
+            for args in itertools.product(*children):
+                value = tuple(component(x, args) for x in rule.components)
+                if value not in tuples.setdefault(rule.lhs, set()):
+                    tuples[rule.lhs].add(value)
+                    changed = True
+    return {x[0] for x in tuples.get(mcfg.start, set())}
+
+
+def test_mcfg() -> None:
+    lexicon = Lexicon("John::d\nMary::d\nruns::=d v")
+    mcfg = lexicon.to_mcfg("v")
+    assert "S(x0_0 x0_1 x0_2) :- [v]" in mcfg.to_text().split("\n")
+    assert mcfg.dimensions()["S"] == 1
+    assert mcfg.dimensions()["[v]"] == 3
+    assert mcfg_language(mcfg) == {"John runs", "Mary runs"}
+    entries = {str(x): x.entries for x in mcfg.rules}
+    assert entries["[d](ε, John, ε)."] == ["John::d"]
+    assert entries["[d](ε, Mary, ε)."] == ["Mary::d"]
+    assert entries["S(x0_0 x0_1 x0_2) :- [v]"] == []
+    (rule,) = [x for x in mcfg.rules if x.lhs == "[v]"]
+    assert rule.rhs == ["[=d v]", "[d]"]
+    assert rule.entries == ["John::d", "Mary::d", "runs::=d v"]
+
+    lexicon = Lexicon("John::d\nruns::=d v\nodd::=z d")
+    (rule,) = [x for x in lexicon.to_mcfg("v").rules if x.lhs == "[v]"]
+    assert rule.entries == ["John::d", "runs::=d v"]
+
+    lexicon = Lexicon("John::d\nruns::=d v\ns::=>v t")
+    text = lexicon.to_mcfg("t").to_text().split("\n")
+    assert "[t](x0_0, x1_1-x0_1, x0_2 x1_0 x1_2) :- [=>v t], [v]" in text
+
+    lexicon = Lexicon("who::d -wh\nJohn::d\nsaw::d= =d v\n::=v +wh c")
+    mcfg = lexicon.to_mcfg("c")
+    assert mcfg.dimensions()["[+wh c | -wh]"] == 4
+    assert mcfg_language(mcfg) == {
+        " ".join(x) for x, _ in lexicon.generate_unique_strings("c")
+    }
+
+
+def test_ngram_model() -> None:
+    lexicon = Lexicon("John::d @ 3\nMary::d\nruns::=d v")
+    tokens = lexicon.tokens()
+    sos, eos = tokens["[SOS]"], tokens["[EOS]"]
+
+    bigrams = lexicon.ngram_model("v", 2)
+    assert bigrams.shape == (len(tokens), len(tokens))
+    assert abs(bigrams[sos, tokens["John"]] - math.log(0.75)) < 1e-9
+    assert abs(bigrams[sos, tokens["Mary"]] - math.log(0.25)) < 1e-9
+    assert bigrams[tokens["John"], tokens["runs"]] == 0
+    assert bigrams[tokens["runs"], eos] == 0
+    assert bigrams[tokens["runs"], tokens["John"]] == float("-inf")
+
+    trigrams = lexicon.ngram_model("v", 3)
+    assert trigrams.shape == (len(tokens),) * 3
+    assert trigrams[sos, tokens["Mary"], tokens["runs"]] == 0
+
+    unigrams = lexicon.ngram_model("v", 1)
+    assert abs(unigrams[tokens["runs"]] - math.log(1 / 3)) < 1e-9
+
+    with pytest.raises(MGError):
+        lexicon.ngram_model("v", 20)
+
+
+def test_automaton() -> None:
+    lexicon = Lexicon("John::d\nMary::d\nruns::=d v\nsees::d= =d v")
+    tokens = lexicon.tokens()
+    sos, eos = tokens["[SOS]"], tokens["[EOS]"]
+    john, mary, runs, sees = (tokens[w] for w in ["John", "Mary", "runs", "sees"])
+
+    automaton = lexicon.to_automaton("v", 3)
+    assert automaton.accepts([sos, john, runs, eos])
+    assert automaton.accepts([sos, mary, sees, john, eos, tokens["[PAD]"]])
+    assert not automaton.accepts([sos, john, eos])
+    assert not automaton.accepts([sos, runs, john, eos])
+    assert not automaton.accepts([john, runs])
+
+    for s, _ in lexicon.generate_unique_strings("v"):
+        if len(s) <= 3:
+            assert automaton.accepts([sos, *(tokens[w] for w in s), eos])
+
+    mask = automaton.next_tokens([sos, john])
+    assert mask.shape == (len(tokens),)
+    assert set(mask.nonzero()[0]) == {runs, sees}
+    assert set(automaton.next_tokens([sos, john, runs]).nonzero()[0]) == {eos}
+    assert set(automaton.next_tokens([]).nonzero()[0]) == {sos}
+
+    # John and Mary lead to the same state after minimisation.
+    assert automaton.n_states == 4
+    assert automaton.to_dot().startswith("digraph")
+    assert lexicon.to_automaton("v", 1).n_states == 1
+
+    lexicon = Lexicon("cafe\u0301::d\nruns::=d v")
+    assert '[label="cafe\u0301"]' in lexicon.to_automaton("v", 4).to_dot()
+
+    lexicon = Lexicon("well-known::a\nJohn::=a d\nruns::=d v")
+    tokens = lexicon.tokens()
+    automaton = lexicon.to_automaton("v", 3)
+    assert automaton.accepts(
+        [tokens["[SOS]"], tokens["well-known"], tokens["John"], tokens["runs"], tokens["[EOS]"]]
+    )
+
+
+def test_feature_graph() -> None:
+    lexicon = Lexicon(
+        "John::d\nwho::d -wh\nruns::=d v\nsees::d= =d v\n::=v +wh c\n::=v c"
+    )
+    graph = lexicon.feature_graph()
+
+    G: rx.PyDiGraph[FeatureNode, FeatureEdge] = rx.PyDiGraph()
+    G.add_nodes_from([node for _, node in graph.nodes()])
+    G.add_edges_from(graph.edges())
+    assert sorted(str(node) for node in G.nodes()) == ["-wh", "c", "d", "v"]
+
+    index = {str(node): i for i, node in graph.nodes()}
+    edges = {(src, tgt): edge for src, tgt, edge in graph.edges()}
+    assert edges[(index["v"], index["d"])].kind == "select"
+    assert sorted(edges[(index["v"], index["d"])].entries) == [
+        "runs::=d v",
+        "sees::d= =d v",
+    ]
+    assert edges[(index["c"], index["-wh"])].kind == "license"
+    assert edges[(index["-wh"], index["d"])].kind == "provide"
+    assert edges[(index["-wh"], index["d"])].entries == ["who::d -wh"]
+    assert len(edges) == 4
+    assert graph.to_dot().startswith("digraph")
+    assert '[label="runs::=d v\\nsees::d= =d v", style=solid]' in graph.to_dot()
+
+
+def test_grammar_template() -> None:
+    template = GrammarTemplate(
+        "John::d\nMary::d\n[wh: who::d -wh]\nsees::[head_initial: d= | =d] =d v\n::=v [wh: +wh] c"
+    )
+    assert template.parameters == ["wh", "head_initial"]
+    assert template.grammar({"wh": False, "head_initial": True}) == (
+        "John::d\nMary::d\n\nsees::d= =d v\n::=v  c"
+    )
+
+    variants = template.expand()
+    assert [x.vector for x in variants] == [
+        [False, False],
+        [False, True],
+        [True, False],
+        [True, True],
+    ]
+    assert variants[1].parameters == {"wh": False, "head_initial": True}
+    assert variants[0].lexicon.parse("John Mary sees", "c")
+    assert not variants[0].lexicon.parse("John sees Mary", "c")
+    assert variants[1].lexicon.parse("John sees Mary", "c")
+    assert variants[3].lexicon.parse("who John sees", "c")
+    assert not variants[3].lexicon.parse("John sees Mary", "c")
+
+    selected = template.expand([{"wh": True, "head_initial": False}])
+    assert len(selected) == 1
+    assert selected[0].lexicon.parse("who John sees", "c")
+    assert template.instantiate({"wh": True, "head_initial": False}).vector == [
+        True,
+        False,
+    ]
+
+    with pytest.raises(MGError):
+        _ = template.instantiate({"wh": True})
+    with pytest.raises(MGError):
+        _ = template.instantiate({"wh": True, "head_initial": True, "v_to_t": True})
+    with pytest.raises(GrammarSyntaxError) as e:
+        _ = GrammarTemplate("John::d\nsees::[head_initial: d= =d v")
+    assert e.value.line == 2
+
+    # Removed entries leave blank lines, including at the start of the grammar.
+    blank = GrammarTemplate("[wh: who::d -wh]\nJohn::d\n\n[wh: ::=d +wh d]\nruns::=d v")
+    lexicon = blank.instantiate({"wh": False}).lexicon
+    assert lexicon.parse("John runs", "v")
+    assert lexicon.mdl_breakdown(26) == Lexicon("John::d\nruns::=d v").mdl_breakdown(26)
+
+    # Only a | outside of parentheses separates the alternatives.
+    semantic = GrammarTemplate(
+        "John::d::a_John\nruns::=d v::[p: lambda a x (pa_a(x) | pa_b(x)) | lambda a x pa_a(x)]"
+    )
+    assert semantic.grammar({"p": True}).endswith("::lambda a x (pa_a(x) | pa_b(x))")
+    assert semantic.grammar({"p": False}).endswith("::lambda a x pa_a(x)")
+    with pytest.raises(GrammarSyntaxError):
+        _ = GrammarTemplate("runs::=d v::[p: lambda a x pa_a(x) | pa_b(x) | lambda a x pa_a(x)]")
+
+    large = GrammarTemplate("\n".join(["John::d", *(f"[p{i}: x{i}::d]" for i in range(64))]))
+    with pytest.raises(MGError):
+        _ = large.expand()
+    assert len(large.expand([{f"p{i}": False for i in range(64)}])) == 1
+
+
+def test_mutate() -> None:
+    lexicon = Lexicon("John::d\nMary::d\nsees::d= =d v\nruns::=d v")
+    operations = [
+        "add_feature",
+        "remove_feature",
+        "duplicate_entry",
+        "swap_selectors",
+        "toggle_head_movement",
+    ]
+    for operation in operations:
+        for seed in range(5):
+            mutated, description = lexicon.mutate(seed, operations=[operation])
+            assert description.startswith(f"{operation}: ")
+            assert mutated != lexicon
+            assert (mutated, description) == lexicon.mutate(seed, [operation])
+
+    mutated, description = lexicon.mutate(0, operations=["swap_selectors"])
+    assert description == "swap_selectors: sees::d= =d v -> sees::=d d= v"
+    assert mutated == Lexicon("John::d\nMary::d\nsees::=d d= v\nruns::=d v")
+
+    mutated, description = lexicon.mutate(0, operations=["duplicate_entry"])
+    copy = description.split(" -> ")[1]
+    assert copy.endswith("_0")
+    assert mutated == Lexicon("John::d\nMary::d\nsees::d= =d v\nruns::=d v\n" + copy)
+
+    toggled, description = Lexicon("John::d\nruns::d= v").mutate(0, ["toggle_head_movement"])
+    assert description == "toggle_head_movement: runs::d= v -> runs::=>d v"
+    assert toggled.mutate(0, ["toggle_head_movement"])[0] == Lexicon("John::d\nruns::d= v")
+    with pytest.raises(MGError):
+        _ = Lexicon("John::d\nruns::=d v").mutate(0, ["toggle_head_movement"])
+    # Toggling either entry would make it the same as the other.
+    with pytest.raises(MGError):
+        _ = Lexicon("John::d\nruns::d= v\nruns::=>d v").mutate(0, ["toggle_head_movement"])
+
+    weighted = Lexicon(
+        "John::d @ 3\nMary::d\nsees::d= =d v",
+        tokens={"[SOS]": 0, "[EOS]": 1, "[PAD]": 2, "[AFFIX]": 3, "sees": 4, "Mary": 5, "John": 6},
+        metadata={"John::d": {"gloss": "John"}},
+    )
+    mutated, _ = weighted.mutate(0, ["toggle_head_movement"])
+    assert mutated.tokens() == weighted.tokens()
+    assert mutated.metadata() == {"John::d": {"gloss": "John"}}
+    assert mutated.weights() == {"John::d": 0.75, "Mary::d": 0.25, "sees::=>d =d v": 1.0}
+
+    mutated, description = weighted.mutate(0, ["duplicate_entry"])
+    copy = description.split(" -> ")[1]
+    if description.startswith("duplicate_entry: John::d"):
+        assert mutated.metadata()[copy] == {"gloss": "John"}
+    weights = mutated.weights()
+    assert weights is not None and weights[copy] == 1.0
+
+    with pytest.raises(MGError):
+        _ = lexicon.mutate(0, operations=["crossover"])
+    with pytest.raises(MGError):
+        _ = Lexicon("John::d").mutate(0, operations=["swap_selectors"])
+
+
+def test_restrict() -> None:
+    lexicon = Lexicon(
+        "John::d\nMary::d\nwho::d -wh\nruns::=d v\nsees::d= =d v\n::=v c\n::=v +wh c"
+    )
+
+    restricted = lexicon.restrict(words=["John", "runs"])
+    assert restricted.tokens() == lexicon.tokens()
+    assert [str(x) for x in restricted.generate_grammar("c")] == ["John runs"]
+    assert restricted == Lexicon(
+        "John::d\nruns::=d v\n::=v c", tokens=lexicon.tokens()
+    )
+    pickle_assert(restricted)
+
+    wh = lexicon.restrict(words=["John", "who", "sees"])
+    assert wh.parse("who John sees", "c")
+    assert wh.tokens() == lexicon.tokens()
+
+    chosen = lexicon.restrict(entries=["Mary::d", "runs::=d v", "::=v c"])
+    assert [str(x) for x in chosen.generate_grammar("c")] == ["Mary runs"]
+
+    weighted = Lexicon("John::d @ 3\nMary::d\nSue::d\nruns::=d v")
+    weights = weighted.restrict(words=["John", "Mary", "runs"]).weights()
+    assert weights is not None
+    assert abs(weights["John::d"] - 0.75) < 1e-9
+
+    with pytest.raises(MGError):
+        _ = lexicon.restrict(words=["sees"])
+    with pytest.raises(MGError):
+        _ = Lexicon("John::d\nruns::=d v", tokens={"John": 4})
+
+
+def test_union() -> None:
+    clause = Lexicon("runs::=d v\nsees::d= =d v\n::=v c")
+    dp = Lexicon("the::n= d\ndog::n\ncat::n\nJohn::d")
+
+    union = clause.union(dp)
+    assert union.clashes == []
+    assert union.renamed == {}
+    assert union.homophones == []
+    assert not union.semantics_dropped
+    assert union.lexicon.parse("the dog sees John", "c")
+    tokens = union.lexicon.tokens()
+    assert all(tokens[w] == i for w, i in clause.tokens().items())
+    pickle_assert(union.lexicon)
+
+    other = Lexicon("runs::=n v\nMary::n")
+    merged = clause.union(other)
+    assert merged.clashes == ["v"]
+    assert merged.homophones == [("runs::=d v", "runs::=n v")]
+    assert merged.lexicon.parse("Mary runs", "v")
+
+    renamed = clause.union(other, rename_conflicts=True)
+    assert renamed.renamed == {"v": "v_0"}
+    assert renamed.lexicon.parse("Mary runs", "v_0")
+    assert not renamed.lexicon.parse("Mary runs", "v")
+
+    namespaced = clause.union(other, rename_conflicts=True, namespace="frag")
+    assert namespaced.renamed == {"v": "frag_v"}
+    assert namespaced.lexicon.parse("Mary runs", "frag_v")
+
+    taken = Lexicon("runs::=d v\nsees::d= =d frag_v\n::=v c")
+    collision = taken.union(other, rename_conflicts=True, namespace="frag")
+    assert collision.renamed == {"v": "frag_v_0"}
+    assert collision.lexicon.parse("Mary runs", "frag_v_0")
+    assert not collision.lexicon.parse("Mary runs", "frag_v")
+
+    semantic = Lexicon("Sue::d::a_Sue")
+    mixed = clause.union(semantic)
+    assert mixed.semantics_dropped
+    assert not mixed.lexicon.is_semantic()
+    assert mixed.lexicon.parse("Sue runs", "v")
+
+
+def test_diff() -> None:
+    old = Lexicon("John::d\nMary::d\nruns::=d v\nsees::d= =d v\n::=v c")
+    new = Lexicon(
+        "John::d\nSue::d\nwho::d -wh\nruns::=d +wh v\nsees::=d d= v\n::=v c\n::=v +wh c"
+    )
+
+    assert old.diff(old).is_empty()
+    diff = old.diff(new)
+    assert diff.added == {
+        "Sue": ["Sue::d"],
+        "who": ["who::d -wh"],
+        "": ["::=v +wh c"],
+    }
+    assert diff.removed == {"Mary": ["Mary::d"]}
+    assert set(diff.modified) == {"runs", "sees"}
+
+    [runs] = diff.modified["runs"]
+    assert (runs.before, runs.after) == ("runs::=d v", "runs::=d +wh v")
+    assert runs.added_features == ["+wh"]
+    assert runs.removed_features == []
+    assert not runs.reordered
+
+    [sees] = diff.modified["sees"]
+    assert sees.added_features == [] and sees.removed_features == []
+    assert sees.reordered
+
+    semantic = Lexicon("John::d::a_John\nruns::=d v::lambda a x pe_run(x)")
+    changed = Lexicon("John::d::a_John\nruns::=d v::lambda a x pe_sleep(x)")
+    [change] = semantic.diff(changed).modified["runs"]
+    assert change.semantics_before == "lambda a x pe_run(x)"
+    assert change.semantics_after == "lambda a x pe_sleep(x)"
+    assert change.added_features == []
+
+
+def test_from_file(tmp_path) -> None:
+    (tmp_path / "dp.mg").write_text(
+        "# Determiner phrases\nJohn::d\n    @gloss a person called John\n"
+        "    @tags name animate\n\nMary::d  # another name\n    @weight 2.0\n"
+    )
+    (tmp_path / "main.mg").write_text(
+        "include dp.mg\n\nruns::=d v\n    @gloss to run\nsees::d= =d v @ 0.5\n"
+    )
+
+    lexicon = Lexicon.from_file(tmp_path / "main.mg")
+    metadata = {
+        "John::d": {"gloss": "a person called John", "tags": "name animate"},
+        "runs::=d v": {"gloss": "to run"},
+    }
+    assert lexicon.metadata() == metadata
+    assert lexicon == Lexicon(
+        "John::d\nMary::d @ 2.0\nruns::=d v\nsees::d= =d v @ 0.5",
+        metadata=metadata,
+    )
+    assert lexicon.weights() == pytest.approx(
+        {
+            "John::d": 1 / 3,
+            "Mary::d": 2 / 3,
+            "runs::=d v": 2 / 3,
+            "sees::d= =d v": 1 / 3,
+        }
+    )
+    assert lexicon.parse("John sees Mary", "v")
+    pickle_assert(lexicon)
+    assert pickle.loads(pickle.dumps(lexicon)).metadata() == lexicon.metadata()
+
+    (tmp_path / "bad.mg").write_text("John::d\n\n# oops\nruns::d=+ v\n")
+    (tmp_path / "broken.mg").write_text("include bad.mg\n")
+    with pytest.raises(GrammarSyntaxError) as e:
+        Lexicon.from_file(tmp_path / "broken.mg")
+    assert e.value.file == str((tmp_path / "bad.mg").resolve())
+    assert e.value.line == 4
+    assert e.value.entry == "runs::d=+ v"
+
+    (tmp_path / "loop.mg").write_text("include loop.mg\n")
+    with pytest.raises(GrammarSyntaxError) as e:
+        Lexicon.from_file(tmp_path / "loop.mg")
+    assert e.value.line == 1
+
+    (tmp_path / "orphan.mg").write_text("@gloss nothing\nJohn::d\n")
+    with pytest.raises(GrammarSyntaxError):
+        Lexicon.from_file(tmp_path / "orphan.mg")
+
+    # Columns count characters, so the ideographic space is one column.
+    (tmp_path / "wide.mg").write_text("\u3000@gloss nothing\nJohn::d\n", encoding="utf-8")
+    with pytest.raises(GrammarSyntaxError) as e:
+        Lexicon.from_file(tmp_path / "wide.mg")
+    assert e.value.column == 2
+
+    (tmp_path / "weight.mg").write_text("John::d\nruns::=d v\n    @weight lots\n")
+    with pytest.raises(GrammarSyntaxError) as e:
+        Lexicon.from_file(tmp_path / "weight.mg")
+    assert e.value.line == 3
+    assert e.value.column == 5
+
+    (tmp_path / "sharp.mg").write_text("C#::n # a note\n#plays::=n v\nplays::=n v\n")
+    sharp = Lexicon.from_file(tmp_path / "sharp.mg")
+    assert sharp == Lexicon("C#::n\nplays::=n v")
+    assert sharp.parse("C# plays", "v")
+
+
+def test_json() -> None:
+    lexicon = Lexicon(
+        "John::d::a_John\n::=d v::lambda a x pe_run(x)",
+        metadata={"John::d::a_John": {"gloss": "John"}},
+    )
+    s = lexicon.to_json()
+    data = json.loads(s)
+    assert data["semantic"]
+    assert data["entries"] == [
+        {
+            "lemma": "John",
+            "features": ["d"],
+            "semantics": "a_John",
+            "metadata": {"gloss": "John"},
+        },
+        {"lemma": None, "features": ["=d", "v"], "semantics": "lambda a x pe_run(x)"},
+    ]
+    assert data["tokens"] == lexicon.tokens()
+    assert Lexicon.from_json(s) == lexicon
+
+    weighted = Lexicon("John::d @ 3\nMary::d\nruns::=d v")
+    assert Lexicon.from_json(weighted.to_json()) == weighted
+    tenths = Lexicon("John::d @ 0.1\nMary::d @ 0.2\nSue::d @ 0.3\nruns::=d v")
+    assert json.loads(tenths.to_json())["entries"][0]["weight"] == 0.1
+    assert Lexicon.from_json(tenths.to_json()) == tenths
+    assert Lexicon.from_json(tenths.to_json()).weights() == tenths.weights()
+    pickle_assert(tenths)
+    with pytest.raises(MGError):
+        _ = Lexicon.from_json("{")
+    with pytest.raises(MGError):
+        _ = Lexicon.from_json(
+            '{"semantic": false, "entries": [{"lemma": "a", "features": ["=>"], '
+            '"semantics": null}], "tokens": {}}'
+        )
+
+    grammar = Lexicon(
+        "::T= C\n::T= +W C\ns::=>V =D T\ndrink::D= V\nbeer::N\nqueen::N\n"
+        "the::N= D\nwhich::N= D -W"
+    )
+    parses = grammar.parse("which beer the queen drink-s", "C") + grammar.parse(
+        "the queen drink-s the beer", "C", move_prob=0.9
+    )
+    assert len(parses) == 2
+    for parse in parses:
+        s = parse.to_json()
+        data = json.loads(s)
+        assert data["string"] == parse.pronunciation()
+        assert data["log_prob"] == parse.log_prob()
+        assert SyntacticStructure.from_json(s, grammar) == parse
+        assert SyntacticStructure.from_json(s, Lexicon.from_json(grammar.to_json())) == parse
+
+    with pytest.raises(MGError):
+        _ = SyntacticStructure.from_json(s, weighted)
+
+
+def test_structure_pickling() -> None:
+    lexicon = Lexicon("John::d\nMary::d\nsees::d= =d v\nwho::d -wh\n::=v +wh c\n::=v c")
+    parses = lexicon.parse("who John sees", "c") + lexicon.parse("John sees Mary", "c")
+    for parse in parses:
+        pickle_assert(parse)
+
+    copy = pickle.loads(pickle.dumps(parses))
+    assert copy == parses
+    assert copy[0].contains_word("who")
+
+    again = lexicon.parse("John sees Mary", "c")
+    assert hash(again[0]) == hash(parses[-1])
+    assert len(set(parses + again)) == len(parses)
+    counts = {parse: 0 for parse in parses}
+    counts[again[0]] += 1
+    assert counts[parses[-1]] == 1
+
+
+def test_fingerprint() -> None:
+    lexicon = Lexicon("John::d\nMary::d\nruns::=d v")
+    fingerprint = lexicon.fingerprint()
+    assert len(fingerprint) == 32
+    assert int(fingerprint, 16) >= 0
+    assert fingerprint == "{:032x}".format(int(fingerprint, 16))
+
+    reordered = Lexicon("runs::=d v\n\nMary::d\nJohn::d\n")
+    assert reordered.fingerprint() == fingerprint
+    assert Lexicon("John::d\nMary::d\nruns::d= v").fingerprint() != fingerprint
+    assert Lexicon("John::d @ 3\nMary::d\nruns::=d v").fingerprint() != fingerprint
+    assert (
+        Lexicon("John::d::a_John\nruns::=d v::lambda a x pe_run(x)").fingerprint()
+        != Lexicon("John::d::a_John\nruns::=d v::lambda a x pe_sleep(x)").fingerprint()
+    )
+
+    copy = Lexicon("John::d\nMary::d\nruns::=d v")
+    assert hash(copy) == hash(lexicon)
+    assert len({lexicon, copy}) == 1
+    cache = {lexicon: "parses"}
+    assert cache[copy] == "parses"
+
+    weighted = Lexicon("a::d @ 0.1\nb::d @ 0.2\nc::d @ 0.3\nd::d @ 0.7\nruns::=d v")
+    shuffled = Lexicon("d::d @ 0.7\nruns::=d v\nc::d @ 0.3\na::d @ 0.1\nb::d @ 0.2")
+    assert shuffled.fingerprint() == weighted.fingerprint()
+    assert hash(shuffled) == hash(weighted)
+    assert shuffled.weights() == weighted.weights()
+
+
+def test_steps() -> None:
+    lexicon = Lexicon(
+        "::T= C\n::T= +W C\ns::=>V =D T\ndrink::D= V\nbeer::N\nqueen::N\n"
+        "the::N= D\nwhich::N= D -W"
+    )
+    [parse] = lexicon.parse("which beer the queen drink-s", "C")
+    steps = parse.steps()
+    summary = [(step.operation, step.feature or "", step.lexeme) for step in steps]
+    assert sorted(summary) == sorted(
+        [
+            ("lexical_insertion", "", "::T= +W C"),
+            ("lexical_insertion", "", "the::N= D"),
+            ("lexical_insertion", "", "queen::N"),
+            ("merge_right", "N=", "the::N= D"),
+            ("lexical_insertion", "", "s::=>V =D T"),
+            ("lexical_insertion", "", "drink::D= V"),
+            ("lexical_insertion", "", "which::N= D -W"),
+            ("lexical_insertion", "", "beer::N"),
+            ("merge_right", "N=", "which::N= D -W"),
+            ("merge_right", "D=", "drink::D= V"),
+            ("head_move", "=>V", "s::=>V =D T"),
+            ("merge_left", "=D", "s::=>V =D T"),
+            ("merge_right", "T=", "::T= +W C"),
+            ("move", "+W", "::T= +W C"),
+        ]
+    )
+    # Each head is inserted before it checks its features, which it checks in order.
+    for entry, features in [("s::=>V =D T", ["=>V", "=D"]), ("::T= +W C", ["T=", "+W"])]:
+        checks = [i for i, step in enumerate(steps) if step.lexeme == entry]
+        assert steps[checks[0]].operation == "lexical_insertion"
+        assert [steps[i].feature for i in checks[1:]] == features
+    # The mover is merged (and held in memory) before it moves.
+    merged = summary.index(("merge_right", "D=", "drink::D= V"))
+    assert steps[merged].memory == ["-W"]
+    assert merged < len(steps) - 1
+    assert summary[-1] == ("move", "+W", "::T= +W C")
+    assert steps[-1].memory == []
+    assert all(isinstance(step, DerivationStep) for step in steps)
+    assert str(steps[-1]) == "move +W: ::T= +W C []"
+
+    [single] = Lexicon("John::d").parse("John", "d")
+    assert [step.operation for step in single.steps()] == ["lexical_insertion"]
+
+    semantic = Lexicon("John::d::a_John\nJohn::d::a_Jon\nruns::=d v::lambda a x pe_run(x)")
+    parses = semantic.parse("John runs", "v")
+    assert parses
+    for parse in parses:
+        lexemes = [step.lexeme for step in parse.steps() if step.operation == "lexical_insertion"]
+        assert sorted(lexemes) == ["John::d", "runs::=d v"]
+
+
+def test_memory_load_profile() -> None:
+    lexicon = Lexicon(
+        "::T= C\n::T= +W C\ns::=>V =D T\ndrink::D= V\nbeer::N\nqueen::N\n"
+        "the::N= D\nwhich::N= D -W"
+    )
+    [parse] = lexicon.parse("which beer the queen drink-s", "C")
+    profile = parse.memory_load_profile()
+    assert profile.movers == [len(step.memory) for step in parse.steps()]
+    assert max(profile.movers) == 1
+    assert profile.movers[-1] == 0
+
+    assert profile.words == parse.pronunciation()
+    assert profile.tenure == [1, 2, 1, 2, 1]
+    assert profile.stack_size == [2, 1, 2, 1, 1]
+    assert profile.max_tenure == 4
+    assert profile.sum_tenure == 25
+    assert profile.max_stack_size == 3
+
+    [single] = Lexicon("John::d").parse("John", "d")
+    profile = single.memory_load_profile()
+    assert (profile.movers, profile.words, profile.tenure) == ([0], ["John"], [0])
+
+
+FIXTURE_GRAMMARS = [
+    "a::b= a\nb::b\nc::b",
+    "John::d\nMary::d\nruns::=d v\nsees::d= =d v",
+    "who::d -wh\nJohn::d\nsaw::d= =d v\n::=v +wh c",
+    "::T= C\n::T= +W C\ns::=>V =D T\ndrink::D= V\nbeer::N\nqueen::N\n"
+    "the::N= D\nwhich::N= D -W",
+    "::T<= +q Q\nwhat::d[in] -subj3 -q -wh\n::q -q\ndoes::V= q= +subj3 T\nrun::v",
+    "John::d::a_John\n::=d v::lambda a x pe_run(x)",
+]
+
+
+def test_entry_parsers_agree() -> None:
+    for grammar in FIXTURE_GRAMMARS:
+        entries = json.loads(Lexicon(grammar).to_json())["entries"]
+        lines = [line.split("::") for line in grammar.split("\n")]
+        assert len(entries) == len(lines)
+        for entry, (lemma, features, *semantics) in zip(entries, lines):
+            assert entry["lemma"] == (lemma or None)
+            assert entry["features"] == features.split()
+            assert entry.get("semantics") == (semantics[0] if semantics else None)
+
+
 def pickle_assert(
     x,  # noqa: ANN001  # pyright: ignore[reportUnknownParameterType, reportMissingParameterType]
 ) -> None:
@@ -26,6 +969,8 @@ def pickle_assert(
 
 def test_pickling() -> None:
     pickle_assert(Lexicon("a::b= a\nb::b"))
+    pickle_assert(Lexicon("a::b= a\nb::b @ 2\nc::b"))
+    pickle_assert(Lexicon("John::d::a_John\nruns::=d v::lambda a x pe_run(x)"))
     pickle_assert(Actor("John", {"a", "b"}))
 
     pickle_assert(Actor("John", properties={"a", "b"}))
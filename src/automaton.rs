@@ -0,0 +1,334 @@
+use std::collections::{BTreeMap, HashMap};
+
+use numpy::PyArray1;
+use pyo3::prelude::*;
+
+use minimalist_grammar_parser::{PhonContent, parsing::beam::Continuation};
+
+use crate::{
+    PyLexicon,
+    errors::MGError,
+    tokenizers::{AFFIX, EOS, PAD, SOS},
+    weights::Search,
+};
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+struct State {
+    transitions: BTreeMap<usize, usize>,
+    accepting: bool,
+}
+
+///A deterministic finite automaton over token IDs which recognizes the strings of a grammar up to
+///a maximum length.
+///
+///Tokens are as in :meth:`python_mg.SyntacticStructure.tokens`: the automaton starts after the
+///[SOS] token, and a string can end with [EOS] in any accepting state.
+#[pyclass(name = "Automaton", module = "python_mg", frozen)]
+#[derive(Debug, Clone, PartialEq)]
+pub struct PyAutomaton {
+    states: Vec<State>,
+    vocabulary: Vec<String>,
+}
+
+impl PyAutomaton {
+    ///Merges equivalent states of an acyclic automaton whose states are numbered so that every
+    ///transition goes to a later state, leaving the start state as state 0. States which cannot
+    ///reach an accepting state are removed (apart from the start state).
+    fn minimise(states: Vec<State>, vocabulary: Vec<String>) -> Self {
+        let mut ids: HashMap<State, usize> = HashMap::new();
+        let mut merged = vec![];
+        let mut new_id = vec![None; states.len()];
+        for (i, state) in states.into_iter().enumerate().rev() {
+            let state = State {
+                transitions: state
+                    .transitions
+                    .into_iter()
+                    .filter_map(|(token, target)| Some((token, new_id[target]?)))
+                    .collect(),
+                accepting: state.accepting,
+            };
+            if i != 0 && !state.accepting && state.transitions.is_empty() {
+                continue;
+            }
+            new_id[i] = Some(*ids.entry(state.clone()).or_insert_with(|| {
+                merged.push(state);
+                merged.len() - 1
+            }));
+        }
+        let start = new_id[0].expect("The start state is never removed");
+
+        //Renumber the states breadth first from the start state.
+        let mut order = vec![start];
+        let mut renumbered: HashMap<usize, usize> = HashMap::from([(start, 0)]);
+        let mut i = 0;
+        while i < order.len() {
+            for target in merged[order[i]].transitions.values() {
+                if !renumbered.contains_key(target) {
+                    renumbered.insert(*target, order.len());
+                    order.push(*target);
+                }
+            }
+            i += 1;
+        }
+        let states = order
+            .into_iter()
+            .map(|i| State {
+                transitions: merged[i]
+                    .transitions
+                    .iter()
+                    .map(|(token, target)| (*token, renumbered[target]))
+                    .collect(),
+                accepting: merged[i].accepting,
+            })
+            .collect();
+
+        PyAutomaton { states, vocabulary }
+    }
+
+    ///The state reached after reading `tokens` (without [SOS]), if any.
+    fn run(&self, tokens: &[usize]) -> Option<&State> {
+        let mut state = &self.states[0];
+        for token in tokens {
+            state = &self.states[*state.transitions.get(token)?];
+        }
+        Some(state)
+    }
+}
+
+///Quotes a string for the DOT language, escaping quotes, backslashes and newlines.
+pub(crate) fn dot_quote(s: &str) -> String {
+    let mut quoted = String::from("\"");
+    for c in s.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+#[pymethods]
+impl PyAutomaton {
+    ///Checks whether a sequence of tokens is a string of the grammar.
+    ///
+    ///Parameters
+    ///----------
+    ///tokens : list of int or ndarray of int
+    ///    The tokens, starting with [SOS] and ending with [EOS] (optionally followed by [PAD]).
+    ///
+    ///Returns
+    ///-------
+    ///bool
+    ///    Whether the automaton accepts the tokens.
+    fn accepts(&self, tokens: Vec<usize>) -> bool {
+        let mut end = tokens.len();
+        while end > 0 && tokens[end - 1] == PAD {
+            end -= 1;
+        }
+        if end < 2 || tokens[0] != SOS || tokens[end - 1] != EOS {
+            return false;
+        }
+        self.run(&tokens[1..end - 1])
+            .is_some_and(|state| state.accepting)
+    }
+
+    ///Gets which tokens may follow a prefix.
+    ///
+    ///Parameters
+    ///----------
+    ///prefix : list of int or ndarray of int
+    ///    The tokens of the prefix, starting with [SOS].
+    ///
+    ///Returns
+    ///-------
+    ///ndarray of bool, shape (C,)
+    ///    Boolean mask of the tokens which may follow the prefix, where C is the number of tokens
+    ///    in the grammar vocabulary. An empty prefix may only be followed by [SOS].
+    fn next_tokens<'py>(&self, py: Python<'py>, prefix: Vec<usize>) -> Bound<'py, PyArray1<bool>> {
+        let mut mask = vec![false; self.vocabulary.len()];
+        match prefix.split_first() {
+            None => mask[SOS] = true,
+            Some((&SOS, rest)) => {
+                if let Some(state) = self.run(rest) {
+                    for token in state.transitions.keys() {
+                        mask[*token] = true;
+                    }
+                    mask[EOS] = state.accepting;
+                }
+            }
+            Some(_) => (),
+        }
+        PyArray1::from_vec(py, mask)
+    }
+
+    ///The number of states of the automaton.
+    #[getter]
+    fn n_states(&self) -> usize {
+        self.states.len()
+    }
+
+    ///Converts the automaton to the DOT language of Graphviz.
+    ///
+    ///Returns
+    ///-------
+    ///str
+    ///    The automaton in DOT. State 0 is the start state and accepting states are drawn with a
+    ///    double circle.
+    fn to_dot(&self) -> String {
+        let mut dot = "digraph {\n    rankdir=LR;\n    start [shape=point];\n".to_string();
+        for (i, state) in self.states.iter().enumerate() {
+            let shape = if state.accepting {
+                "doublecircle"
+            } else {
+                "circle"
+            };
+            dot.push_str(&format!("    {i} [shape={shape}];\n"));
+        }
+        dot.push_str("    start -> 0;\n");
+        for (i, state) in self.states.iter().enumerate() {
+            for (token, target) in &state.transitions {
+                dot.push_str(&format!(
+                    "    {i} -> {target} [label={}];\n",
+                    dot_quote(&self.vocabulary[*token])
+                ));
+            }
+        }
+        dot.push('}');
+        dot
+    }
+
+    fn __repr__(&self) -> String {
+        format!("Automaton(n_states={})", self.states.len())
+    }
+}
+
+impl PyLexicon {
+    ///The words which can follow `prefix` along with their tokens, in order of their tokens, and
+    ///whether `prefix` is itself a string of the lexicon. Words are kept whole rather than written
+    ///out, as a lemma may itself contain a hyphen.
+    #[allow(clippy::type_complexity)]
+    fn next_tokens(
+        &self,
+        category: &str,
+        prefix: &[PhonContent<String>],
+        search: &Search,
+    ) -> PyResult<(Vec<(Vec<usize>, PhonContent<String>)>, bool)> {
+        let prefix: Vec<PhonContent<&str>> = prefix
+            .iter()
+            .map(|x| match x {
+                PhonContent::Normal(x) => PhonContent::Normal(x.as_str()),
+                PhonContent::Affixed(items) => {
+                    PhonContent::Affixed(items.iter().map(|x| x.as_str()).collect())
+                }
+            })
+            .collect();
+        let token = |word: &str| {
+            self.word_id
+                .get(word)
+                .ok_or_else(|| MGError::new_err(format!("{word} is not a word of the lexicon")))
+        };
+
+        let mut words = vec![];
+        let mut ends = false;
+        for continuation in self.continuations_of(category, &prefix, search)? {
+            match continuation {
+                Continuation::Word(word) => {
+                    words.push((vec![token(&word)?], PhonContent::Normal(word)))
+                }
+                Continuation::AffixedWord(items) => {
+                    let mut tokens = vec![];
+                    for (i, item) in items.iter().enumerate() {
+                        if i != 0 {
+                            tokens.push(AFFIX);
+                        }
+                        tokens.push(token(item)?);
+                    }
+                    words.push((tokens, PhonContent::Affixed(items)));
+                }
+                Continuation::EndOfSentence => ends = true,
+            }
+        }
+        words.sort_by(|a, b| a.0.cmp(&b.0));
+        Ok((words, ends))
+    }
+}
+
+#[pymethods]
+impl PyLexicon {
+    #[allow(clippy::too_many_arguments)]
+    #[pyo3(signature = (category, max_len, min_log_prob=None, move_prob=0.5, max_steps=64, n_beams=None))]
+    ///Compiles the strings of the grammar of up to ``max_len`` words into a minimal deterministic
+    ///automaton over token IDs, which can check membership without parsing.
+    ///
+    ///Parameters
+    ///----------
+    ///category : str
+    ///    The syntactic category of the strings.
+    ///max_len : int
+    ///    The maximum number of words of the strings. Affixed words count as one word.
+    ///min_log_prob : float or None, optional
+    ///    Minimum log probability threshold for the parser to consider
+    ///    Default is None.
+    ///move_prob : float, optional
+    ///    Probability of preferring a move over a merge when parsing.
+    ///    Default is 0.5
+    ///max_steps : int or None, optional
+    ///    Maximum number of derivation steps. If None, will not be limited.
+    ///    Default is 64.
+    ///n_beams : int or None, optional
+    ///    Number of beams to maintain while parsing. If None, will not be limited.
+    ///    Default is None.
+    ///
+    ///Returns
+    ///-------
+    ///Automaton
+    ///    The automaton recognizing exactly the strings of the grammar of up to ``max_len`` words.
+    fn to_automaton(
+        &self,
+        category: &str,
+        max_len: usize,
+        min_log_prob: Option<f64>,
+        move_prob: f64,
+        max_steps: Option<usize>,
+        n_beams: Option<usize>,
+    ) -> PyResult<PyAutomaton> {
+        let search = self.search(min_log_prob, move_prob, max_steps, n_beams)?;
+        let mut states = vec![State::default()];
+        let mut frontier: Vec<(Vec<PhonContent<String>>, usize)> = vec![(vec![], 0)];
+        for length in 0..=max_len {
+            let mut next = vec![];
+            for (prefix, state) in frontier {
+                let (words, ends) = self.next_tokens(category, &prefix, &search)?;
+                states[state].accepting = ends;
+                if length == max_len {
+                    continue;
+                }
+                for (tokens, word) in words {
+                    let mut current = state;
+                    for token in tokens {
+                        current = match states[current].transitions.get(&token) {
+                            Some(target) => *target,
+                            None => {
+                                states.push(State::default());
+                                states[current].transitions.insert(token, states.len() - 1);
+                                states.len() - 1
+                            }
+                        };
+                    }
+                    let mut prefix = prefix.clone();
+                    prefix.push(word);
+                    next.push((prefix, current));
+                }
+            }
+            frontier = next;
+        }
+
+        let vocabulary = (0..self.word_id.len())
+            .map(|i| self.word_id.word(i).unwrap_or_default().to_string())
+            .collect();
+        Ok(PyAutomaton::minimise(states, vocabulary))
+    }
+}
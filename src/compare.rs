@@ -0,0 +1,167 @@
+use std::collections::{HashMap, HashSet};
+
+use itertools::Itertools;
+use minimalist_grammar_parser::PhonContent;
+use pyo3::prelude::*;
+
+use crate::{
+    PyLexicon, errors::MGError, mdl::log_sum_exp, phon_content_to_string, weights::Search,
+};
+
+///How different the string distributions of two lexicons are.
+///
+///Divergences are measured in bits, between the distributions found by a bounded search of each
+///lexicon (renormalised to sum to one).
+#[pyclass(name = "LexiconComparison", module = "python_mg", frozen, get_all)]
+#[derive(Debug, Clone, PartialEq)]
+pub struct PyLexiconComparison {
+    ///The Kullback-Leibler divergence of the other lexicon from this one, KL(self || other). It is
+    ///``inf`` if this lexicon generates a string the other does not.
+    kl_divergence: f64,
+    ///The Kullback-Leibler divergence of this lexicon from the other one, KL(other || self).
+    reverse_kl_divergence: f64,
+    ///The Jensen-Shannon divergence between the two lexicons, between 0 and 1.
+    js_divergence: f64,
+    ///The total variation distance between the two lexicons, between 0 and 1.
+    total_variation: f64,
+    ///The strings generated by both lexicons.
+    shared: HashSet<String>,
+    ///The strings only generated by this lexicon.
+    only_self: HashSet<String>,
+    ///The strings only generated by the other lexicon.
+    only_other: HashSet<String>,
+}
+
+#[pymethods]
+impl PyLexiconComparison {
+    fn __repr__(&self) -> String {
+        format!(
+            "LexiconComparison(kl_divergence={}, js_divergence={}, total_variation={}, shared={}, only_self={}, only_other={})",
+            self.kl_divergence,
+            self.js_divergence,
+            self.total_variation,
+            self.shared.len(),
+            self.only_self.len(),
+            self.only_other.len()
+        )
+    }
+}
+
+///`p log2(p / q)`, treating `0 log 0` as 0.
+fn kl_term(p: f64, q: f64) -> f64 {
+    if p == 0.0 { 0.0 } else { p * (p / q).log2() }
+}
+
+impl PyLexicon {
+    ///The normalised probability of each string generated by the lexicon.
+    fn normalised_strings<'a>(
+        &'a self,
+        category: &str,
+        search: &Search,
+        max_strings: Option<usize>,
+    ) -> PyResult<HashMap<Vec<PhonContent<&'a str>>, f64>> {
+        let distribution = self.string_distribution(category, search, max_strings)?;
+        let total = log_sum_exp(distribution.values().map(|x| x.into_inner()));
+        if total == f64::NEG_INFINITY {
+            return Err(MGError::new_err(format!(
+                "No strings of {category} were generated"
+            )));
+        }
+        Ok(distribution
+            .into_iter()
+            .map(|(s, p)| (s, (p.into_inner() - total).exp()))
+            .collect())
+    }
+}
+
+#[pymethods]
+impl PyLexicon {
+    #[allow(clippy::too_many_arguments)]
+    #[pyo3(signature = (other, category, min_log_prob=None, move_prob=0.5, max_steps=64, n_beams=None, max_strings=None))]
+    ///Compares the distribution over strings of this lexicon with that of another lexicon.
+    ///
+    ///Each distribution is the marginal probability of every string, as in
+    ///:meth:`python_mg.Lexicon.generate_unique_strings`, renormalised over the strings that were
+    ///found.
+    ///
+    ///Parameters
+    ///----------
+    ///other : Lexicon
+    ///    The lexicon to compare to.
+    ///category : str
+    ///    The syntactic category to be generated.
+    ///min_log_prob : float or None, optional
+    ///    Minimum log probability threshold to be generated.
+    ///    If none, there is no limit on log probability.
+    ///    Default is None.
+    ///move_prob : float, optional
+    ///    Probability of preferring a move over a merge when parsing.
+    ///    Default is 0.5
+    ///max_steps : int or None, optional
+    ///    Maximum number of derivation steps. If None, will not be limited.
+    ///    Default is 64.
+    ///n_beams : int or None, optional
+    ///    Number of beams to maintain while parsing. If None, will not be limited.
+    ///    Default is None.
+    ///max_strings : int or None, optional
+    ///    Number of strings to generate from each lexicon before stopping.
+    ///    Default is None.
+    ///
+    ///Returns
+    ///-------
+    ///LexiconComparison
+    ///    The divergences between the two distributions, and which strings they share.
+    fn compare(
+        &self,
+        other: &PyLexicon,
+        category: &str,
+        min_log_prob: Option<f64>,
+        move_prob: f64,
+        max_steps: Option<usize>,
+        n_beams: Option<usize>,
+        max_strings: Option<usize>,
+    ) -> PyResult<PyLexiconComparison> {
+        let p = self.normalised_strings(
+            category,
+            &self.search(min_log_prob, move_prob, max_steps, n_beams)?,
+            max_strings,
+        )?;
+        let q = other.normalised_strings(
+            category,
+            &other.search(min_log_prob, move_prob, max_steps, n_beams)?,
+            max_strings,
+        )?;
+
+        //Strings are compared word by word, as an affixed word is written the same as a lemma
+        //containing a hyphen.
+        let strings: HashSet<&Vec<PhonContent<&str>>> = p.keys().chain(q.keys()).collect();
+        let mut comparison = PyLexiconComparison {
+            kl_divergence: 0.0,
+            reverse_kl_divergence: 0.0,
+            js_divergence: 0.0,
+            total_variation: 0.0,
+            shared: HashSet::new(),
+            only_self: HashSet::new(),
+            only_other: HashSet::new(),
+        };
+
+        for s in strings {
+            let p_s = p.get(s).copied().unwrap_or(0.0);
+            let q_s = q.get(s).copied().unwrap_or(0.0);
+            let m_s = (p_s + q_s) / 2.0;
+
+            comparison.kl_divergence += kl_term(p_s, q_s);
+            comparison.reverse_kl_divergence += kl_term(q_s, p_s);
+            comparison.js_divergence += (kl_term(p_s, m_s) + kl_term(q_s, m_s)) / 2.0;
+            comparison.total_variation += (p_s - q_s).abs() / 2.0;
+
+            let string = s.iter().map(phon_content_to_string).join(" ");
+            match (p.contains_key(s), q.contains_key(s)) {
+                (true, true) => comparison.shared.insert(string),
+                (true, false) => comparison.only_self.insert(string),
+                _ => comparison.only_other.insert(string),
+            };
+        }
+        Ok(comparison)
+    }
+}
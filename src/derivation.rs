@@ -0,0 +1,248 @@
+use std::{collections::HashMap, fmt::Display};
+
+use itertools::Itertools;
+use minimalist_grammar_parser::{
+    Direction, RulePool,
+    lexicon::LexemeId,
+    parsing::rules::{Rule, RuleIndex, TreeEdge},
+};
+use pyo3::prelude::*;
+
+use crate::{
+    PyLexicon,
+    entries::{Entry, Feature, FeatureKind},
+    errors::MGError,
+    syntax::PySyntacticStructure,
+};
+
+///One step of a derivation, as returned by :meth:`python_mg.SyntacticStructure.steps`.
+#[pyclass(
+    name = "DerivationStep",
+    module = "python_mg",
+    frozen,
+    get_all,
+    eq,
+    str
+)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PyDerivationStep {
+    ///The operation, out of ``"lexical_insertion"``, ``"merge_left"``, ``"merge_right"``,
+    ///``"head_move"`` (merging with a selector which moves the head of its complement) and
+    ///``"move"``.
+    operation: String,
+    ///The feature of the head which was checked, or None for lexical insertion.
+    feature: Option<String>,
+    ///The lexical entry which was inserted, or else the entry of the head of the result (e.g.
+    ///``"the::N= D"``), without its semantics. Entries which only differ in their semantics are
+    ///the same lexeme to the parser, so they cannot be told apart here.
+    lexeme: String,
+    ///The features of each mover in memory after the step.
+    pub(crate) memory: Vec<String>,
+}
+
+impl Display for PyDerivationStep {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.operation)?;
+        if let Some(feature) = &self.feature {
+            write!(f, " {feature}")?;
+        }
+        write!(f, ": {} [{}]", self.lexeme, self.memory.join(", "))
+    }
+}
+
+#[pymethods]
+impl PyDerivationStep {
+    fn __repr__(&self) -> String {
+        format!("DerivationStep({self})")
+    }
+}
+
+///The tree of a derivation, with the children of each node from left to right.
+pub(crate) struct Tree {
+    pub(crate) root: usize,
+    pub(crate) labels: Vec<String>,
+    pub(crate) traces: Vec<Option<usize>>,
+    pub(crate) stolen: Vec<bool>,
+    pub(crate) children: Vec<Vec<usize>>,
+}
+
+impl Tree {
+    pub(crate) fn new(lexicon: &PyLexicon, rules: &RulePool) -> Self {
+        let derivation = lexicon.lexicon.lexicon().derivation(rules.clone());
+        let tree = derivation.tree();
+        let (g, root) = tree.petgraph();
+
+        let mut labels = vec![];
+        let mut traces: Vec<Option<usize>> = vec![];
+        let mut stolen = vec![];
+        for n in g.node_indices() {
+            let node = g
+                .node_weight(n)
+                .unwrap()
+                .clone()
+                .map(|x| x.to_string(), |x| x.to_string());
+            labels.push(node.to_string());
+            traces.push(node.trace_id().map(|x| x.into()));
+            stolen.push(node.lemma().is_some_and(|x| x.is_stolen()));
+        }
+
+        let mut children: Vec<Vec<(bool, usize)>> = vec![vec![]; labels.len()];
+        for e in g.edge_indices() {
+            let (src, tgt) = g.edge_endpoints(e).unwrap();
+            match g.edge_weight(e).unwrap() {
+                TreeEdge::Merge(direction) => {
+                    children[src.index()].push((matches!(direction, Direction::Right), tgt.index()))
+                }
+                TreeEdge::Move | TreeEdge::MoveHead => (),
+            }
+        }
+
+        Tree {
+            root: root.index(),
+            labels,
+            traces,
+            stolen,
+            children: children
+                .into_iter()
+                .map(|x| x.into_iter().sorted().map(|(_, n)| n).collect())
+                .collect(),
+        }
+    }
+}
+
+///A constituent built by the derivation so far.
+struct Constituent<'a> {
+    features: &'a [Feature],
+    ///The entry of the head of the constituent.
+    lexeme: &'a Entry,
+    memory: Vec<&'a [Feature]>,
+}
+
+///Takes the constituent built by the rule `id`, which is part of a later step.
+fn take<'a>(built: &mut HashMap<usize, Constituent<'a>>, id: &RuleIndex) -> Constituent<'a> {
+    built
+        .remove(&usize::from(*id))
+        .expect("The parts of a step are derived before it")
+}
+
+impl PyLexicon {
+    ///The steps of the derivation `rules`, from the bottom up. The parser finds derivations from
+    ///the top down, so the steps are the rules of the derivation in reverse.
+    pub(crate) fn derivation_steps(&self, rules: &RulePool) -> PyResult<Vec<PyDerivationStep>> {
+        let mut entries: HashMap<LexemeId, &Entry> = HashMap::new();
+        for entry in &self.entries {
+            entries.insert(self.lexeme_id(entry)?, entry);
+        }
+
+        let rules: Vec<&Rule> = rules.iter().collect();
+        let mut built: HashMap<usize, Constituent> = HashMap::new();
+        let mut steps = vec![];
+        for (i, rule) in rules.iter().enumerate().rev() {
+            let (head, complement) = match rule {
+                Rule::Scan { lexeme, .. } => {
+                    let entry = *entries.get(lexeme).ok_or_else(|| {
+                        MGError::new_err("The derivation uses a lexeme which is not in the lexicon")
+                    })?;
+                    steps.push(PyDerivationStep {
+                        operation: "lexical_insertion".to_string(),
+                        feature: None,
+                        lexeme: entry.syntax(),
+                        memory: vec![],
+                    });
+                    built.insert(
+                        i,
+                        Constituent {
+                            features: &entry.features,
+                            lexeme: entry,
+                            memory: vec![],
+                        },
+                    );
+                    continue;
+                }
+                Rule::Unmerge {
+                    child_id,
+                    complement_id,
+                    ..
+                } => (
+                    take(&mut built, child_id),
+                    Some(take(&mut built, complement_id)),
+                ),
+                Rule::UnmergeFromMover {
+                    child_id,
+                    stored_id,
+                    ..
+                } => (
+                    take(&mut built, child_id),
+                    Some(take(&mut built, stored_id)),
+                ),
+                Rule::Unmove { child_id, .. } | Rule::UnmoveFromMover { child_id, .. } => {
+                    (take(&mut built, child_id), None)
+                }
+                Rule::Start { .. } | Rule::UnmoveTrace(_) => continue,
+            };
+
+            let Constituent {
+                features,
+                lexeme,
+                mut memory,
+            } = head;
+            let (feature, features) = features
+                .split_first()
+                .expect("The head of a step has a feature to check");
+            match complement {
+                Some(complement) => {
+                    memory.extend(complement.memory);
+                    if complement.features.len() > 1 {
+                        memory.push(&complement.features[1..]);
+                    }
+                }
+                None => {
+                    let licensee = Feature::new(FeatureKind::Licensee, feature.name.clone());
+                    if let Some(i) = memory.iter().position(|x| x.first() == Some(&licensee)) {
+                        memory[i] = &memory[i][1..];
+                        if memory[i].is_empty() {
+                            memory.remove(i);
+                        }
+                    }
+                }
+            }
+
+            steps.push(PyDerivationStep {
+                operation: match feature.kind {
+                    FeatureKind::LeftSelector => "merge_left",
+                    FeatureKind::RightSelector => "merge_right",
+                    FeatureKind::LeftAffix | FeatureKind::RightAffix => "head_move",
+                    _ => "move",
+                }
+                .to_string(),
+                feature: Some(feature.to_string()),
+                lexeme: lexeme.syntax(),
+                memory: memory.iter().map(|x| x.iter().join(" ")).collect(),
+            });
+            built.insert(
+                i,
+                Constituent {
+                    features,
+                    lexeme,
+                    memory,
+                },
+            );
+        }
+        Ok(steps)
+    }
+}
+
+#[pymethods]
+impl PySyntacticStructure {
+    ///The steps of the derivation of this structure, from the bottom up, in the order the
+    ///derivation takes them. A mover is merged before it moves.
+    ///
+    ///Returns
+    ///-------
+    ///list of DerivationStep
+    ///    Each step, naming the operation, the feature it checked, the lexical entry involved and
+    ///    the movers held in memory after it.
+    fn steps(&self) -> PyResult<Vec<PyDerivationStep>> {
+        self.lex().get().derivation_steps(self.rules())
+    }
+}
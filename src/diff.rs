@@ -0,0 +1,175 @@
+use std::collections::{BTreeMap, HashMap};
+
+use pyo3::prelude::*;
+
+use crate::{PyLexicon, entries::Entry};
+
+///How an entry of a lexicon was changed in another lexicon.
+#[pyclass(name = "EntryChange", module = "python_mg", frozen, eq, get_all)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PyEntryChange {
+    ///The entry in the first lexicon.
+    before: String,
+    ///The entry in the second lexicon.
+    after: String,
+    ///The features of ``before`` which are not in ``after``.
+    removed_features: Vec<String>,
+    ///The features of ``after`` which are not in ``before``.
+    added_features: Vec<String>,
+    ///Whether the features shared by both entries are in a different order.
+    reordered: bool,
+    ///The semantic term of ``before``, if it has one.
+    semantics_before: Option<String>,
+    ///The semantic term of ``after``, if it has one.
+    semantics_after: Option<String>,
+}
+
+#[pymethods]
+impl PyEntryChange {
+    fn __repr__(&self) -> String {
+        format!("EntryChange({:?} -> {:?})", self.before, self.after)
+    }
+}
+
+///The differences between two lexicons, with entries keyed by their lemma (``""`` for entries
+///without a pronunciation).
+#[pyclass(name = "LexiconDiff", module = "python_mg", frozen, get_all)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PyLexiconDiff {
+    ///The entries which are only in the second lexicon.
+    added: HashMap<String, Vec<String>>,
+    ///The entries which are only in the first lexicon.
+    removed: HashMap<String, Vec<String>>,
+    ///The entries of the first lexicon which were changed in the second.
+    modified: HashMap<String, Vec<PyEntryChange>>,
+}
+
+#[pymethods]
+impl PyLexiconDiff {
+    ///Whether the two lexicons have the same entries.
+    ///
+    ///Returns
+    ///-------
+    ///bool
+    ///    ``True`` if nothing was added, removed or modified.
+    fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.modified.is_empty()
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "LexiconDiff(added={}, removed={}, modified={})",
+            self.added.values().map(Vec::len).sum::<usize>(),
+            self.removed.values().map(Vec::len).sum::<usize>(),
+            self.modified.values().map(Vec::len).sum::<usize>()
+        )
+    }
+}
+
+///Splits the features of `a` into those which are also in `b` and those which are not, counting
+///repeated features.
+fn split(a: &Entry, b: &Entry) -> (Vec<String>, Vec<String>) {
+    let mut remaining = b.features.clone();
+    let mut shared = vec![];
+    let mut difference = vec![];
+    for feature in &a.features {
+        match remaining.iter().position(|x| x == feature) {
+            Some(i) => {
+                remaining.remove(i);
+                shared.push(feature.to_string());
+            }
+            None => difference.push(feature.to_string()),
+        }
+    }
+    (shared, difference)
+}
+
+impl PyEntryChange {
+    fn new(before: &Entry, after: &Entry) -> Self {
+        let (shared_before, removed_features) = split(before, after);
+        let (shared_after, added_features) = split(after, before);
+        PyEntryChange {
+            before: before.to_string(),
+            after: after.to_string(),
+            removed_features,
+            added_features,
+            reordered: shared_before != shared_after,
+            semantics_before: before.semantics.clone(),
+            semantics_after: after.semantics.clone(),
+        }
+    }
+
+    ///How different the two entries are.
+    fn distance(&self) -> usize {
+        self.removed_features.len()
+            + self.added_features.len()
+            + usize::from(self.reordered)
+            + usize::from(self.semantics_before != self.semantics_after)
+    }
+}
+
+#[pymethods]
+impl PyLexicon {
+    ///Compares the entries of this lexicon with those of another lexicon.
+    ///
+    ///Entries with the same lemma which are only in one of the lexicons are paired up, most
+    ///similar first, as modifications of each other. Any which are left over are added or removed.
+    ///
+    ///Parameters
+    ///----------
+    ///other : Lexicon
+    ///    The new version of the lexicon.
+    ///
+    ///Returns
+    ///-------
+    ///LexiconDiff
+    ///    The added, removed and modified entries of ``other``, keyed by lemma.
+    fn diff(&self, other: &PyLexicon) -> PyLexiconDiff {
+        let mut lemmas: BTreeMap<String, (Vec<&Entry>, Vec<&Entry>)> = BTreeMap::new();
+        for entry in self.entries.iter().filter(|x| !other.entries.contains(x)) {
+            let lemma = entry.lemma.clone().unwrap_or_default();
+            lemmas.entry(lemma).or_default().0.push(entry);
+        }
+        for entry in other.entries.iter().filter(|x| !self.entries.contains(x)) {
+            let lemma = entry.lemma.clone().unwrap_or_default();
+            lemmas.entry(lemma).or_default().1.push(entry);
+        }
+
+        let mut diff = PyLexiconDiff {
+            added: HashMap::new(),
+            removed: HashMap::new(),
+            modified: HashMap::new(),
+        };
+        for (lemma, (mut removed, mut added)) in lemmas {
+            let mut modified = vec![];
+            while !removed.is_empty() && !added.is_empty() {
+                let (i, j, change) = removed
+                    .iter()
+                    .enumerate()
+                    .flat_map(|(i, a)| {
+                        added
+                            .iter()
+                            .enumerate()
+                            .map(move |(j, b)| (i, j, PyEntryChange::new(a, b)))
+                    })
+                    .min_by_key(|(_, _, change)| change.distance())
+                    .expect("Both sides are non-empty");
+                removed.remove(i);
+                added.remove(j);
+                modified.push(change);
+            }
+
+            let strings = |x: Vec<&Entry>| x.into_iter().map(|x| x.to_string()).collect();
+            if !removed.is_empty() {
+                diff.removed.insert(lemma.clone(), strings(removed));
+            }
+            if !added.is_empty() {
+                diff.added.insert(lemma.clone(), strings(added));
+            }
+            if !modified.is_empty() {
+                diff.modified.insert(lemma, modified);
+            }
+        }
+        diff
+    }
+}
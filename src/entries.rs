@@ -0,0 +1,236 @@
+use std::fmt::Display;
+
+///The kinds of syntactic features a lexical entry can have, written as they are in a grammar
+///string.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub(crate) enum FeatureKind {
+    ///`x`
+    Category,
+    ///`x=`, selects its complement to the right.
+    RightSelector,
+    ///`=x`, selects its complement to the left.
+    LeftSelector,
+    ///`=>x`, selects and steals the head of its complement.
+    LeftAffix,
+    ///`x<=`, selects and steals the head of its complement.
+    RightAffix,
+    ///`+x`
+    Licensor,
+    ///`-x`
+    Licensee,
+}
+
+impl FeatureKind {
+    ///Whether the feature is a selector (with or without head movement).
+    pub(crate) fn is_selector(&self) -> bool {
+        matches!(
+            self,
+            FeatureKind::RightSelector
+                | FeatureKind::LeftSelector
+                | FeatureKind::LeftAffix
+                | FeatureKind::RightAffix
+        )
+    }
+
+    ///The selector which selects the same complement with head movement, or without it. Head
+    ///movement takes its complement on the right, so `x=` becomes `=>x` while `=>x` and `x<=`
+    ///become `x=`. `=x` has no counterpart.
+    pub(crate) fn toggle_head_movement(&self) -> Option<FeatureKind> {
+        match self {
+            FeatureKind::RightSelector => Some(FeatureKind::LeftAffix),
+            FeatureKind::LeftAffix | FeatureKind::RightAffix => Some(FeatureKind::RightSelector),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub(crate) struct Feature {
+    pub kind: FeatureKind,
+    pub name: String,
+}
+
+impl Display for Feature {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = &self.name;
+        match self.kind {
+            FeatureKind::Category => write!(f, "{name}"),
+            FeatureKind::RightSelector => write!(f, "{name}="),
+            FeatureKind::LeftSelector => write!(f, "={name}"),
+            FeatureKind::LeftAffix => write!(f, "=>{name}"),
+            FeatureKind::RightAffix => write!(f, "{name}<="),
+            FeatureKind::Licensor => write!(f, "+{name}"),
+            FeatureKind::Licensee => write!(f, "-{name}"),
+        }
+    }
+}
+
+impl Feature {
+    pub(crate) fn new(kind: FeatureKind, name: impl Into<String>) -> Self {
+        Feature {
+            kind,
+            name: name.into(),
+        }
+    }
+
+    ///Reads a single feature, e.g. `=d` or `-wh`.
+    pub(crate) fn parse(s: &str) -> Option<Feature> {
+        let (kind, name) = if let Some(name) = s.strip_prefix("=>") {
+            (FeatureKind::LeftAffix, name)
+        } else if let Some(name) = s.strip_suffix("<=") {
+            (FeatureKind::RightAffix, name)
+        } else if let Some(name) = s.strip_prefix('=') {
+            (FeatureKind::LeftSelector, name)
+        } else if let Some(name) = s.strip_suffix('=') {
+            (FeatureKind::RightSelector, name)
+        } else if let Some(name) = s.strip_prefix('+') {
+            (FeatureKind::Licensor, name)
+        } else if let Some(name) = s.strip_prefix('-') {
+            (FeatureKind::Licensee, name)
+        } else {
+            (FeatureKind::Category, s)
+        };
+
+        if name.is_empty() || name.contains(['=', '+', '-', '<', '>', ':']) {
+            None
+        } else {
+            Some(Feature::new(kind, name))
+        }
+    }
+}
+
+///A lexical entry as it is written in a grammar string. Unlike
+///[`minimalist_grammar_parser::lexicon::LexicalEntry`] this keeps the semantic term (if there is
+///one) and can be freely edited before being turned back into a grammar.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub(crate) struct Entry {
+    pub lemma: Option<String>,
+    pub features: Vec<Feature>,
+    pub semantics: Option<String>,
+}
+
+///Why a line of a grammar could not be read as a lexical entry.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct EntryError {
+    ///1-indexed character column of the problem.
+    pub column: usize,
+    pub message: String,
+}
+
+fn column(line: &str, byte_offset: usize) -> usize {
+    line[..byte_offset].chars().count() + 1
+}
+
+fn byte_offset(line: &str, part: &str) -> usize {
+    part.as_ptr() as usize - line.as_ptr() as usize
+}
+
+impl Entry {
+    ///Reads a single line of a grammar.
+    pub(crate) fn parse(line: &str) -> Result<Entry, EntryError> {
+        let mut parts = line.splitn(3, "::");
+        let lemma = parts.next().unwrap_or_default().trim();
+        let Some(features) = parts.next() else {
+            return Err(EntryError {
+                column: column(line, line.trim_end().len()),
+                message: "Expected '::' between the lemma and its features".to_string(),
+            });
+        };
+        let semantics = parts.next().map(|x| x.trim().to_string());
+
+        let mut parsed_features: Vec<Feature> = vec![];
+        let mut seen_category = false;
+        for token in features.split_whitespace() {
+            let col = column(line, byte_offset(line, token));
+            let feature = Feature::parse(token).ok_or_else(|| EntryError {
+                column: col,
+                message: format!("'{token}' is not a valid feature"),
+            })?;
+
+            let valid_position = if feature.kind == FeatureKind::Licensee {
+                seen_category
+            } else {
+                !seen_category
+            };
+
+            if !valid_position {
+                return Err(EntryError {
+                    column: col,
+                    message: if seen_category {
+                        format!("'{token}' comes after the category of the entry")
+                    } else {
+                        format!("'{token}' comes before the category of the entry")
+                    },
+                });
+            }
+            seen_category |= feature.kind == FeatureKind::Category;
+            parsed_features.push(feature);
+        }
+
+        if !seen_category {
+            return Err(EntryError {
+                column: column(
+                    line,
+                    byte_offset(line, features) + features.trim_end().len(),
+                ),
+                message: "The entry has no category".to_string(),
+            });
+        }
+
+        Ok(Entry {
+            lemma: match lemma {
+                "" | "ε" => None,
+                _ => Some(lemma.to_string()),
+            },
+            features: parsed_features,
+            semantics,
+        })
+    }
+
+    ///The category feature of the entry.
+    pub(crate) fn category(&self) -> &str {
+        self.features
+            .iter()
+            .find(|x| x.kind == FeatureKind::Category)
+            .map(|x| x.name.as_str())
+            .expect("Entries always have a category")
+    }
+
+    ///Renames every occurrence of a category, whether as the category of the entry or as
+    ///something it selects.
+    pub(crate) fn rename_category(&mut self, from: &str, to: &str) {
+        for feature in self.features.iter_mut() {
+            if (feature.kind == FeatureKind::Category || feature.kind.is_selector())
+                && feature.name == from
+            {
+                feature.name = to.to_string();
+            }
+        }
+    }
+
+    ///The entry without its semantic term, as accepted by
+    ///[`minimalist_grammar_parser::lexicon::LexicalEntry::parse`].
+    pub(crate) fn syntax(&self) -> String {
+        let lemma = self.lemma.as_deref().unwrap_or_default();
+        let features = self.features.iter().map(|x| x.to_string());
+        format!("{lemma}::{}", features.collect::<Vec<_>>().join(" "))
+    }
+}
+
+impl Display for Entry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.syntax())?;
+        if let Some(semantics) = &self.semantics {
+            write!(f, "::{semantics}")?;
+        }
+        Ok(())
+    }
+}
+
+///Whether any entry of a grammar string has a semantic term, i.e. a second `::`. A lemma which
+///contains `::` looks the same, which is why lexicons can be told whether they are semantic.
+pub(crate) fn has_semantics(grammar: &str) -> bool {
+    grammar
+        .lines()
+        .any(|line| line.splitn(3, "::").nth(2).is_some())
+}
@@ -0,0 +1,308 @@
+use std::fmt::Display;
+
+use minimalist_grammar_parser::lexicon::LexicalEntry;
+use pyo3::{create_exception, exceptions::PyValueError, prelude::*};
+use simple_semantics::{
+    lambda::RootedLambdaPool,
+    language::{Expr, LanguageError},
+};
+
+use crate::entries::Entry;
+
+create_exception!(
+    python_mg,
+    MGError,
+    PyValueError,
+    "The base class of all errors raised by python_mg."
+);
+
+create_exception!(
+    python_mg,
+    GrammarSyntaxError,
+    MGError,
+    "Raised when a grammar string cannot be parsed.
+
+Attributes
+----------
+line : int or None
+    The 1-indexed line of the grammar with the error, if it could be found.
+column : int or None
+    The 1-indexed column of the error in that line, if it could be found.
+entry : str or None
+    The text of the offending lexical entry.
+file : str or None
+    The file with the error, if the grammar was read with :meth:`python_mg.Lexicon.from_file`.
+parser : Literal['syntax', 'lot']
+    Whether the syntactic features or the language of thought term of the entry failed to parse."
+);
+
+create_exception!(
+    python_mg,
+    LOTSyntaxError,
+    MGError,
+    "Raised when a string is not a valid language of thought expression or scenario."
+);
+
+create_exception!(
+    python_mg,
+    LOTTypeError,
+    MGError,
+    "Raised when a language of thought expression is badly typed, e.g. when applying a function to an argument of the wrong type or when an expression cannot be fully reduced."
+);
+
+create_exception!(
+    python_mg,
+    PresuppositionError,
+    MGError,
+    "Raised when evaluating a language of thought expression refers to something that does not exist in the scenario."
+);
+
+create_exception!(
+    python_mg,
+    ExecutionLimitError,
+    MGError,
+    "Raised when evaluating a language of thought expression runs out of steps or time."
+);
+
+create_exception!(
+    python_mg,
+    TokenizationError,
+    MGError,
+    "Raised when a sequence of tokens is not a well-formed tokenized string."
+);
+
+///Registers every exception with the module.
+pub(crate) fn add_exceptions(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    let py = m.py();
+    m.add("MGError", py.get_type::<MGError>())?;
+    m.add("GrammarSyntaxError", py.get_type::<GrammarSyntaxError>())?;
+    m.add("LOTSyntaxError", py.get_type::<LOTSyntaxError>())?;
+    m.add("LOTTypeError", py.get_type::<LOTTypeError>())?;
+    m.add("PresuppositionError", py.get_type::<PresuppositionError>())?;
+    m.add("ExecutionLimitError", py.get_type::<ExecutionLimitError>())?;
+    m.add("TokenizationError", py.get_type::<TokenizationError>())?;
+    Ok(())
+}
+
+///Converts a failure to run a language of thought expression into either a
+///[`PresuppositionError`] or an [`ExecutionLimitError`].
+pub(crate) fn execution_error(e: LanguageError) -> PyErr {
+    match e {
+        LanguageError::OutOfFuel | LanguageError::OutOfTime => {
+            ExecutionLimitError::new_err(e.to_string())
+        }
+        _ => PresuppositionError::new_err(e.to_string()),
+    }
+}
+
+///Which of the two parsers of a semantic lexicon rejected an entry.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum GrammarParser {
+    Syntax,
+    Lot,
+}
+
+impl Display for GrammarParser {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            GrammarParser::Syntax => write!(f, "syntax"),
+            GrammarParser::Lot => write!(f, "lot"),
+        }
+    }
+}
+
+///A grammar which could not be parsed, along with where the problem is (if it could be found).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct GrammarError {
+    file: Option<String>,
+    line: Option<usize>,
+    column: Option<usize>,
+    entry: Option<String>,
+    parser: GrammarParser,
+    message: String,
+}
+
+impl Display for GrammarError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if let Some(file) = &self.file {
+            write!(f, "{file}: ")?;
+        }
+        match (self.line, self.column) {
+            (Some(line), Some(column)) => write!(
+                f,
+                "Line {line}, column {column} ({} parser): {}",
+                self.parser, self.message
+            )?,
+            (Some(line), None) => {
+                write!(f, "Line {line} ({} parser): {}", self.parser, self.message)?
+            }
+            _ => write!(f, "{}", self.message)?,
+        }
+        if let Some(entry) = &self.entry {
+            write!(f, "\n\t{entry}")?;
+        }
+        Ok(())
+    }
+}
+
+impl GrammarError {
+    ///An error at a known place in a grammar, found by the syntactic parser.
+    pub(crate) fn at(line: usize, column: usize, entry: &str, message: impl Display) -> Self {
+        GrammarError {
+            file: None,
+            line: Some(line),
+            column: Some(column),
+            entry: Some(entry.trim().to_string()),
+            parser: GrammarParser::Syntax,
+            message: message.to_string(),
+        }
+    }
+
+    ///The same error, in a given file.
+    pub(crate) fn in_file(self, file: impl Display) -> Self {
+        GrammarError {
+            file: Some(file.to_string()),
+            ..self
+        }
+    }
+
+    ///Finds which entry of `grammar` caused `message`, by checking each line on its own.
+    pub(crate) fn locate(grammar: &str, semantic: bool, message: impl Display) -> Self {
+        for (i, line) in grammar.lines().enumerate() {
+            if line.trim().is_empty() {
+                continue;
+            }
+            let mut error = GrammarError {
+                file: None,
+                line: Some(i + 1),
+                column: None,
+                entry: Some(line.trim().to_string()),
+                parser: GrammarParser::Syntax,
+                message: String::new(),
+            };
+
+            let entry = match Entry::parse(line) {
+                Ok(entry) => entry,
+                Err(e) => {
+                    error.column = Some(e.column);
+                    error.message = e.message;
+                    return error;
+                }
+            };
+
+            let features_start = line.find("::").map(|x| x + 2).unwrap_or_default();
+            if let Err(e) = LexicalEntry::parse(entry.syntax().as_str()) {
+                error.column = Some(line[..features_start].chars().count() + 1);
+                error.message = e.to_string();
+                return error;
+            }
+
+            match (semantic, &entry.semantics) {
+                (true, None) => {
+                    error.column = Some(line.trim_end().chars().count() + 1);
+                    error.message = "The entry is missing a semantic term".to_string();
+                    return error;
+                }
+                (false, Some(_)) => {
+                    error.message =
+                        "The entry has a semantic term but the lexicon is not semantic".to_string();
+                    return error;
+                }
+                (true, Some(term)) => {
+                    if let Err(e) = RootedLambdaPool::<Expr>::parse(term.as_str()) {
+                        let term_start = features_start
+                            + line[features_start..]
+                                .find("::")
+                                .map(|x| x + 2)
+                                .unwrap_or_default();
+                        error.column = Some(line[..term_start].chars().count() + 1);
+                        error.parser = GrammarParser::Lot;
+                        error.message = e.to_string();
+                        return error;
+                    }
+                }
+                (false, None) => (),
+            }
+        }
+
+        GrammarError {
+            file: None,
+            line: None,
+            column: None,
+            entry: None,
+            parser: if semantic {
+                GrammarParser::Lot
+            } else {
+                GrammarParser::Syntax
+            },
+            message: message.to_string(),
+        }
+    }
+}
+
+impl From<GrammarError> for PyErr {
+    fn from(value: GrammarError) -> Self {
+        Python::attach(|py| {
+            let err = GrammarSyntaxError::new_err(value.to_string());
+            let exception = err.value(py);
+            let attributes = [
+                exception.setattr("file", value.file),
+                exception.setattr("line", value.line),
+                exception.setattr("column", value.column),
+                exception.setattr("entry", value.entry),
+                exception.setattr("parser", value.parser.to_string()),
+            ];
+            for attr in attributes {
+                attr.expect("Exceptions can have attributes");
+            }
+            err
+        })
+    }
+}
+
+///A failure to build a lexicon. Problems with the grammar itself are kept as a [`GrammarError`] so
+///that they can still be pointed at where the grammar came from.
+#[derive(Debug)]
+pub(crate) enum LexiconError {
+    Grammar(GrammarError),
+    Other(PyErr),
+}
+
+impl LexiconError {
+    ///Points an error about a line of a grammar at the file and line that the line came from, as
+    ///given by `source`. Any other error is returned unchanged.
+    pub(crate) fn relocate(self, source: impl Fn(usize) -> Option<(String, usize)>) -> Self {
+        match self {
+            LexiconError::Grammar(error) => match error.line.and_then(source) {
+                Some((file, line)) => LexiconError::Grammar(GrammarError {
+                    file: Some(file),
+                    line: Some(line),
+                    ..error
+                }),
+                None => LexiconError::Grammar(error),
+            },
+            other => other,
+        }
+    }
+}
+
+impl From<GrammarError> for LexiconError {
+    fn from(value: GrammarError) -> Self {
+        LexiconError::Grammar(value)
+    }
+}
+
+impl From<PyErr> for LexiconError {
+    fn from(value: PyErr) -> Self {
+        LexiconError::Other(value)
+    }
+}
+
+impl From<LexiconError> for PyErr {
+    fn from(value: LexiconError) -> Self {
+        match value {
+            LexiconError::Grammar(error) => error.into(),
+            LexiconError::Other(error) => error,
+        }
+    }
+}
@@ -0,0 +1,184 @@
+use std::{collections::BTreeMap, fmt::Display};
+
+use pyo3::prelude::*;
+
+use crate::{PyLexicon, automaton::dot_quote, entries::FeatureKind};
+
+///A node of a :class:`python_mg.FeatureGraph`, either a category or a licensee feature.
+#[pyclass(
+    name = "FeatureNode",
+    module = "python_mg",
+    frozen,
+    eq,
+    hash,
+    str,
+    get_all
+)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct PyFeatureNode {
+    ///The name of the feature, without any ``-`` prefix.
+    name: String,
+    ///Whether the node is a licensee feature rather than a category.
+    is_licensee: bool,
+}
+
+impl Display for PyFeatureNode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.is_licensee {
+            write!(f, "-{}", self.name)
+        } else {
+            write!(f, "{}", self.name)
+        }
+    }
+}
+
+///An edge of a :class:`python_mg.FeatureGraph`.
+///
+///Edges go from the category of lexical entries to the categories they ``"select"`` and the
+///licensees they ``"license"``, and from licensees to the categories of the entries which
+///``"provide"`` them.
+#[pyclass(name = "FeatureEdge", module = "python_mg", frozen, eq, str, get_all)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PyFeatureEdge {
+    ///One of ``"select"``, ``"license"`` or ``"provide"``.
+    kind: String,
+    ///The lexical entries responsible for the edge.
+    entries: Vec<String>,
+}
+
+impl Display for PyFeatureEdge {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.kind)
+    }
+}
+
+///A graph of how the categories and licensee features of a lexicon depend on each other.
+#[pyclass(name = "FeatureGraph", module = "python_mg", frozen)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PyFeatureGraph {
+    nodes: Vec<PyFeatureNode>,
+    edges: Vec<(usize, usize, PyFeatureEdge)>,
+}
+
+#[pymethods]
+impl PyFeatureGraph {
+    ///The nodes of the graph along with their indices, which can be passed to
+    ///``rustworkx.PyDiGraph.add_nodes_from`` in order.
+    ///
+    ///Returns
+    ///-------
+    ///list of tuple of int and FeatureNode
+    ///    The index and data of each node.
+    fn nodes(&self) -> Vec<(usize, PyFeatureNode)> {
+        self.nodes.iter().cloned().enumerate().collect()
+    }
+
+    ///The edges of the graph, which can be passed to ``rustworkx.PyDiGraph.add_edges_from``.
+    ///
+    ///Returns
+    ///-------
+    ///list of tuple of int, int and FeatureEdge
+    ///    The source, target and data of each edge.
+    fn edges(&self) -> Vec<(usize, usize, PyFeatureEdge)> {
+        self.edges.clone()
+    }
+
+    ///Converts the graph to the DOT language of Graphviz.
+    ///
+    ///Returns
+    ///-------
+    ///str
+    ///    The graph in DOT. Licensees are drawn as boxes and edges are labelled with the entries
+    ///    responsible for them.
+    fn to_dot(&self) -> String {
+        let mut dot = "digraph {\n".to_string();
+        for (i, node) in self.nodes.iter().enumerate() {
+            let shape = if node.is_licensee { "box" } else { "ellipse" };
+            dot.push_str(&format!(
+                "    {i} [label={}, shape={shape}];\n",
+                dot_quote(&node.to_string())
+            ));
+        }
+        for (src, tgt, edge) in &self.edges {
+            let style = match edge.kind.as_str() {
+                "select" => "solid",
+                "license" => "dashed",
+                _ => "dotted",
+            };
+            dot.push_str(&format!(
+                "    {src} -> {tgt} [label={}, style={style}];\n",
+                dot_quote(&edge.entries.join("\n"))
+            ));
+        }
+        dot.push('}');
+        dot
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "FeatureGraph(nodes={}, edges={})",
+            self.nodes.len(),
+            self.edges.len()
+        )
+    }
+}
+
+#[pymethods]
+impl PyLexicon {
+    ///Builds a graph of the categories and licensee features of the lexicon, showing which
+    ///lexical entries select, license or provide them.
+    ///
+    ///Returns
+    ///-------
+    ///FeatureGraph
+    ///    The dependency graph of the features of the lexicon.
+    fn feature_graph(&self) -> PyFeatureGraph {
+        let mut nodes: BTreeMap<PyFeatureNode, usize> = BTreeMap::new();
+        let mut node = |name: &str, is_licensee: bool| {
+            let n = nodes.len();
+            *nodes
+                .entry(PyFeatureNode {
+                    name: name.to_string(),
+                    is_licensee,
+                })
+                .or_insert(n)
+        };
+
+        let mut edges: BTreeMap<(usize, usize, &str), Vec<String>> = BTreeMap::new();
+        for entry in &self.entries {
+            let category = node(entry.category(), false);
+            for feature in &entry.features {
+                let (src, tgt, kind) = match feature.kind {
+                    FeatureKind::Category => continue,
+                    FeatureKind::Licensor => (category, node(&feature.name, true), "license"),
+                    FeatureKind::Licensee => (node(&feature.name, true), category, "provide"),
+                    _ => (category, node(&feature.name, false), "select"),
+                };
+                let entries = edges.entry((src, tgt, kind)).or_default();
+                let entry = entry.to_string();
+                if !entries.contains(&entry) {
+                    entries.push(entry);
+                }
+            }
+        }
+
+        let mut nodes: Vec<_> = nodes.into_iter().collect();
+        nodes.sort_by_key(|(_, i)| *i);
+        PyFeatureGraph {
+            nodes: nodes.into_iter().map(|(node, _)| node).collect(),
+            edges: edges
+                .into_iter()
+                .map(|((src, tgt, kind), entries)| {
+                    (
+                        src,
+                        tgt,
+                        PyFeatureEdge {
+                            kind: kind.to_string(),
+                            entries,
+                        },
+                    )
+                })
+                .collect(),
+        }
+    }
+}
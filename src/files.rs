@@ -0,0 +1,243 @@
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+};
+
+use pyo3::prelude::*;
+
+use crate::{
+    PyLexicon,
+    entries::Entry,
+    errors::{GrammarError, MGError},
+};
+
+///Metadata of lexical entries, keyed by the entry (with its semantics, if it has any).
+pub(crate) type Metadata = HashMap<String, HashMap<String, String>>;
+
+///Keys metadata by the entries it belongs to. Keys without semantics apply to every entry with
+///the same syntax.
+pub(crate) fn match_metadata(entries: &[Entry], metadata: Metadata) -> PyResult<Metadata> {
+    let mut matched: Metadata = HashMap::new();
+    for (key, values) in metadata {
+        let parsed = Entry::parse(&key).map_err(|e| {
+            MGError::new_err(format!(
+                "'{key}' is not a valid lexical entry: {}",
+                e.message
+            ))
+        })?;
+        let mut found = false;
+        for entry in entries.iter().filter(|entry| {
+            if parsed.semantics.is_some() {
+                parsed == **entry
+            } else {
+                parsed.syntax() == entry.syntax()
+            }
+        }) {
+            found = true;
+            matched
+                .entry(entry.to_string())
+                .or_default()
+                .extend(values.clone());
+        }
+        if !found {
+            return Err(MGError::new_err(format!(
+                "'{key}' is not an entry of the grammar"
+            )));
+        }
+    }
+    Ok(matched)
+}
+
+///Removes the comment from a line of a grammar file. Comments start with a `#` at the start of the
+///line or after whitespace, so that entries such as `C#::n` keep their `#`.
+fn strip_comment(line: &str) -> &str {
+    let mut previous: Option<char> = None;
+    for (i, c) in line.char_indices() {
+        if c == '#' && previous.is_none_or(char::is_whitespace) {
+            return &line[..i];
+        }
+        previous = Some(c);
+    }
+    line
+}
+
+///A grammar being read from files, keeping track of where each of its lines came from.
+#[derive(Debug, Default)]
+struct GrammarFile {
+    lines: Vec<String>,
+    sources: Vec<(String, usize)>,
+    metadata: Vec<(usize, String, String)>,
+    stack: Vec<PathBuf>,
+}
+
+impl GrammarFile {
+    fn read(&mut self, path: &Path) -> PyResult<()> {
+        let path = path.canonicalize()?;
+        let file = path.display().to_string();
+        let text = std::fs::read_to_string(&path)?;
+        self.stack.push(path.clone());
+
+        let mut last_entry = None;
+        for (i, raw) in text.lines().enumerate() {
+            let line_number = i + 1;
+            let error = |column: usize, message: &str| -> PyErr {
+                GrammarError::at(line_number, column, raw, message)
+                    .in_file(&file)
+                    .into()
+            };
+            let line = strip_comment(raw);
+            let column = raw.chars().take_while(|c| c.is_whitespace()).count() + 1;
+            let trimmed = line.trim();
+
+            if trimmed.is_empty() {
+                continue;
+            } else if let Some(include) = trimmed.strip_prefix("include ") {
+                let include = include.trim().trim_matches('"');
+                let included = path.parent().unwrap_or(Path::new(".")).join(include);
+                if included
+                    .canonicalize()
+                    .is_ok_and(|x| self.stack.contains(&x))
+                {
+                    return Err(error(column, &format!("'{include}' includes itself")));
+                }
+                if !included.is_file() {
+                    return Err(error(column, &format!("'{include}' is not a file")));
+                }
+                self.read(&included)?;
+                last_entry = None;
+            } else if let Some(metadata) = trimmed.strip_prefix('@') {
+                let Some(entry) = last_entry else {
+                    return Err(error(column, "Metadata must come after an entry"));
+                };
+                let (key, value) = metadata
+                    .trim()
+                    .split_once(char::is_whitespace)
+                    .unwrap_or((metadata.trim(), ""));
+                if key.is_empty() {
+                    return Err(error(column, "Metadata must have a key"));
+                }
+                if key == "weight" {
+                    let weight = value
+                        .trim()
+                        .parse::<f64>()
+                        .map_err(|e| error(column, &format!("Invalid weight: {e}")))?;
+                    if !(weight.is_finite() && weight >= 0.0) {
+                        return Err(error(
+                            column,
+                            &format!("Weights must be finite and non-negative, not {weight}"),
+                        ));
+                    }
+                    let line: &mut String = &mut self.lines[entry];
+                    if line.contains('@') {
+                        return Err(error(column, "The entry already has a weight"));
+                    }
+                    line.push_str(&format!(" @ {weight}"));
+                } else {
+                    self.metadata
+                        .push((entry, key.to_string(), value.trim().to_string()));
+                }
+            } else {
+                last_entry = Some(self.lines.len());
+                self.lines.push(line.trim_end().to_string());
+                self.sources.push((file.clone(), line_number));
+            }
+        }
+
+        self.stack.pop();
+        Ok(())
+    }
+}
+
+#[pymethods]
+impl PyLexicon {
+    #[staticmethod]
+    #[pyo3(signature = (path, semantic=None))]
+    ///Reads a lexicon from a grammar file.
+    ///
+    ///Grammar files have one lexical entry per line, as in :class:`python_mg.Lexicon`, along
+    ///with:
+    ///
+    ///- Comments, from a ``#`` at the start of a line or after whitespace to the end of the line
+    ///  (so ``C#::n`` is an entry).
+    ///- Blank lines, which are ignored.
+    ///- ``include other.mg`` lines, which read the entries of another file (relative to the
+    ///  directory of the file including it).
+    ///- Metadata lines of the form ``@key value``, which belong to the entry before them.
+    ///  ``@weight`` gives the weight of the entry (like ``@ 2.0`` at the end of the entry) and any
+    ///  other key (e.g. ``@gloss`` or ``@tags``) is kept in :meth:`python_mg.Lexicon.metadata`.
+    ///
+    ///Parameters
+    ///----------
+    ///path : str or os.PathLike
+    ///    The path of the file.
+    ///semantic : bool or None, optional
+    ///    Whether the entries have semantic interpretations. If None, the lexicon is semantic if any
+    ///    entry has a semantic term.
+    ///    Default is None.
+    ///
+    ///Returns
+    ///-------
+    ///Lexicon
+    ///    The lexicon of the file.
+    ///
+    ///Raises
+    ///------
+    ///GrammarSyntaxError
+    ///    If the grammar is not a valid lexicon. The ``file`` and ``line`` of the error are those of
+    ///    the original file.
+    ///OSError
+    ///    If the file cannot be read.
+    ///
+    ///Examples
+    ///--------
+    ///.. code-block:: text
+    ///
+    ///    # Determiner phrases
+    ///    include determiners.mg
+    ///
+    ///    John::d
+    ///        @gloss a person called John
+    ///        @tags name animate
+    ///    Mary::d @ 2.0
+    ///    runs::=d v
+    ///        @weight 0.5
+    fn from_file(path: PathBuf, semantic: Option<bool>) -> PyResult<PyLexicon> {
+        let mut grammar = GrammarFile::default();
+        grammar.read(&path)?;
+
+        let mut metadata: Metadata = HashMap::new();
+        for (entry, key, value) in grammar.metadata {
+            let entry = grammar.lines[entry]
+                .rsplit_once('@')
+                .map(|(x, _)| x)
+                .unwrap_or(&grammar.lines[entry])
+                .trim()
+                .to_string();
+            metadata.entry(entry).or_default().insert(key, value);
+        }
+
+        let sources = grammar.sources;
+        PyLexicon::build(
+            grammar.lines.join("\n"),
+            semantic,
+            None,
+            None,
+            Some(metadata),
+        )
+        .map_err(|e| {
+            e.relocate(|line| line.checked_sub(1).and_then(|i| sources.get(i)).cloned())
+                .into()
+        })
+    }
+
+    ///The metadata of the lexical entries, such as glosses or tags, e.g. from
+    ///:meth:`python_mg.Lexicon.from_file`.
+    ///
+    ///Returns
+    ///-------
+    ///dict[str, dict[str, str]]
+    ///    The metadata of each entry which has any, keyed by the entry.
+    fn metadata(&self) -> Metadata {
+        self.metadata.clone()
+    }
+}
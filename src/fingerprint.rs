@@ -0,0 +1,61 @@
+use itertools::Itertools;
+use pyo3::prelude::*;
+
+use crate::PyLexicon;
+
+///128-bit FNV-1a, which (unlike [`std::hash::DefaultHasher`]) gives the same digest on every
+///platform and version of Rust.
+fn fnv1a(bytes: &[u8]) -> u128 {
+    const OFFSET: u128 = 0x6c62272e07bb014262b821756295c58d;
+    const PRIME: u128 = 0x0000000001000000000000000000013b;
+    bytes.iter().fold(OFFSET, |hash, byte| {
+        (hash ^ u128::from(*byte)).wrapping_mul(PRIME)
+    })
+}
+
+impl PyLexicon {
+    ///The lexicon with one normalized entry (and its weight, if it has one) per line, in sorted
+    ///order, so that it does not depend on how the grammar was written. Weights are rounded to 12
+    ///significant digits, so that rounding errors in normalising them do not change the digest.
+    fn normalized(&self) -> String {
+        let weights = self.weights();
+        let entries = self
+            .entries
+            .iter()
+            .map(|entry| {
+                let entry = entry.to_string();
+                match &weights {
+                    Some(weights) => format!("{entry} @ {:.11e}", weights[&entry]),
+                    None => entry,
+                }
+            })
+            .sorted();
+        format!("semantic: {}\n{}", self.is_semantic(), entries.join("\n"))
+    }
+
+    fn digest(&self) -> u128 {
+        fnv1a(self.normalized().as_bytes())
+    }
+}
+
+#[pymethods]
+impl PyLexicon {
+    ///A stable digest of the lexicon, e.g. to tag generated corpora or caches with the grammar
+    ///which made them.
+    ///
+    ///The digest is of the entries (with their semantic terms and weights), so it does not depend
+    ///on the order of the entries or the spacing of the grammar. It is the same on every platform
+    ///and in every session.
+    ///
+    ///Returns
+    ///-------
+    ///str
+    ///    The digest, as 32 hexadecimal digits.
+    pub(crate) fn fingerprint(&self) -> String {
+        format!("{:032x}", self.digest())
+    }
+
+    fn __hash__(&self) -> u64 {
+        self.digest() as u64
+    }
+}
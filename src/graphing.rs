@@ -1,7 +1,9 @@
 use minimalist_grammar_parser::parsing::rules::{TreeEdge, TreeNode};
-use pyo3::{exceptions::PyValueError, prelude::*};
+use pyo3::prelude::*;
 use std::fmt::Display;
 
+use crate::errors::MGError;
+
 ///A node on a tree.
 #[pyclass(name = "MGNode", str, eq, frozen)]
 #[derive(Debug, PartialEq, Eq)]
@@ -25,7 +27,7 @@ impl PyMgNode {
         self.0.is_trace()
     }
 
-    ///Get the trace ID of a trace, if it is one. Otherwise raise a ValueError
+    ///Get the trace ID of a trace, if it is one. Otherwise raise an MGError
     ///
     ///Returns
     ///-------
@@ -35,7 +37,7 @@ impl PyMgNode {
         self.0
             .trace_id()
             .map(|x| x.into())
-            .ok_or(PyValueError::new_err("Not a trace!"))
+            .ok_or(MGError::new_err("Not a trace!"))
     }
 
     ///Get the lemma string of a node, will be ``"Ɛ"`` if the lemma is empty and ``""`` if the node
@@ -0,0 +1,353 @@
+use std::collections::BTreeSet;
+
+use itertools::Itertools;
+use pyo3::prelude::*;
+use rand::{prelude::*, rngs::StdRng};
+
+use crate::{
+    PyLexicon, SelfOwningLexicon,
+    entries::{Entry, Feature, FeatureKind},
+    errors::MGError,
+    get_config,
+    mdl::bits,
+    weights::Search,
+};
+
+///The lexicon which memorises every example: each word selects the rest of its string as its
+///complement.
+fn trivial_lexicon(examples: &[String], category: &str) -> Vec<Entry> {
+    let mut entries = vec![];
+    for (k, example) in examples.iter().enumerate() {
+        let words: Vec<&str> = example.split_whitespace().collect();
+        if words.is_empty() {
+            entries.push(Entry {
+                lemma: None,
+                features: vec![Feature::new(FeatureKind::Category, category)],
+                semantics: None,
+            });
+        }
+        let name = |i: usize| {
+            if i == 0 {
+                category.to_string()
+            } else {
+                format!("x{k}_{i}")
+            }
+        };
+        for (i, word) in words.iter().enumerate() {
+            let mut features = vec![];
+            if i + 1 < words.len() {
+                features.push(Feature::new(FeatureKind::RightSelector, name(i + 1)));
+            }
+            features.push(Feature::new(FeatureKind::Category, name(i)));
+            entries.push(Entry {
+                lemma: Some(word.to_string()),
+                features,
+                semantics: None,
+            });
+        }
+    }
+    entries.into_iter().unique().collect()
+}
+
+fn categories(entries: &[Entry]) -> Vec<String> {
+    entries
+        .iter()
+        .map(|x| x.category().to_string())
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .collect()
+}
+
+///Merges two categories into one.
+fn merge(entries: &[Entry], start: &str, rng: &mut StdRng) -> Option<Vec<Entry>> {
+    let categories = categories(entries);
+    let mergeable: Vec<_> = categories.iter().filter(|x| *x != start).collect();
+    let from = *mergeable.choose(rng)?;
+    let to = categories.iter().filter(|x| *x != from).choose(rng)?;
+    let mut entries = entries.to_vec();
+    for entry in entries.iter_mut() {
+        entry.rename_category(from, to);
+    }
+    Some(entries)
+}
+
+///Moves some of the entries of a category into a new category, duplicating every entry which
+///selects it.
+fn split(entries: &[Entry], rng: &mut StdRng) -> Option<Vec<Entry>> {
+    let categories = categories(entries);
+    let category = categories
+        .iter()
+        .filter(|c| entries.iter().filter(|x| x.category() == *c).count() > 1)
+        .choose(rng)?;
+
+    let mut i = 0;
+    let new_category = loop {
+        let name = format!("{category}_{i}");
+        if !categories.contains(&name) {
+            break name;
+        }
+        i += 1;
+    };
+
+    let mut entries = entries.to_vec();
+    let mut members: Vec<usize> = (0..entries.len())
+        .filter(|i| entries[*i].category() == category)
+        .collect();
+    members.shuffle(rng);
+    for i in &members[..members.len() / 2] {
+        for feature in entries[*i].features.iter_mut() {
+            if feature.kind == FeatureKind::Category {
+                feature.name = new_category.clone();
+            }
+        }
+    }
+
+    let selecting: Vec<Entry> = entries
+        .iter()
+        .filter(|x| {
+            x.features
+                .iter()
+                .any(|f| f.kind.is_selector() && f.name == *category)
+        })
+        .cloned()
+        .collect();
+    for mut entry in selecting {
+        for feature in entry.features.iter_mut() {
+            if feature.kind.is_selector() && feature.name == *category {
+                feature.name = new_category.clone();
+            }
+        }
+        entries.push(entry);
+    }
+    Some(entries)
+}
+
+///Adds a licensor and a matching licensee, so that one entry moves to another.
+fn add_movement(entries: &[Entry], rng: &mut StdRng) -> Option<Vec<Entry>> {
+    let names: BTreeSet<&str> = entries
+        .iter()
+        .flat_map(|x| x.features.iter())
+        .filter(|f| f.kind == FeatureKind::Licensee)
+        .map(|f| f.name.as_str())
+        .collect();
+    let mut i = 0;
+    let fresh = loop {
+        let name = format!("f{i}");
+        if !names.contains(name.as_str()) {
+            break name;
+        }
+        i += 1;
+    };
+    let name = match names.into_iter().choose(rng) {
+        Some(name) if rng.random_bool(0.5) => name.to_string(),
+        _ => fresh,
+    };
+
+    let mut entries = entries.to_vec();
+    let mover = (0..entries.len()).choose(rng)?;
+    let target = (0..entries.len()).choose(rng)?;
+    entries[mover]
+        .features
+        .push(Feature::new(FeatureKind::Licensee, &name));
+    let features = &mut entries[target].features;
+    let category = features
+        .iter()
+        .position(|f| f.kind == FeatureKind::Category)?;
+    features.insert(category, Feature::new(FeatureKind::Licensor, &name));
+    Some(entries)
+}
+
+///Removes every licensor and licensee of some name.
+fn remove_movement(entries: &[Entry], rng: &mut StdRng) -> Option<Vec<Entry>> {
+    let name = entries
+        .iter()
+        .flat_map(|x| x.features.iter())
+        .filter(|f| matches!(f.kind, FeatureKind::Licensor | FeatureKind::Licensee))
+        .map(|f| f.name.clone())
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .choose(rng)?;
+    let mut entries = entries.to_vec();
+    for entry in entries.iter_mut() {
+        entry.features.retain(|f| {
+            !(matches!(f.kind, FeatureKind::Licensor | FeatureKind::Licensee) && f.name == name)
+        });
+    }
+    Some(entries)
+}
+
+///Changes what a selector selects, which direction it selects in, adds or removes movement, or
+///removes an entry.
+fn edit_features(entries: &[Entry], rng: &mut StdRng) -> Option<Vec<Entry>> {
+    match rng.random_range(0..4) {
+        0 => {
+            let mut entries = entries.to_vec();
+            let i = (0..entries.len()).choose(rng)?;
+            entries.remove(i);
+            return Some(entries);
+        }
+        1 => return add_movement(entries, rng),
+        2 => return remove_movement(entries, rng),
+        _ => (),
+    }
+
+    let mut entries = entries.to_vec();
+    let categories = categories(&entries);
+    let selectors: Vec<(usize, usize)> = entries
+        .iter()
+        .enumerate()
+        .flat_map(|(i, x)| {
+            x.features
+                .iter()
+                .enumerate()
+                .filter(|(_, f)| f.kind.is_selector())
+                .map(move |(j, _)| (i, j))
+        })
+        .collect();
+    let (i, j) = *selectors.choose(rng)?;
+    let feature = &mut entries[i].features[j];
+    if rng.random_bool(0.5) {
+        feature.name = categories.choose(rng)?.clone();
+    } else {
+        feature.kind = match feature.kind {
+            FeatureKind::RightSelector => FeatureKind::LeftSelector,
+            FeatureKind::LeftSelector => FeatureKind::RightSelector,
+            kind => kind,
+        };
+    }
+    Some(entries)
+}
+
+struct Induction<'a> {
+    examples: &'a [String],
+    category: &'a str,
+    n_phonemes: u16,
+    search: Search,
+}
+
+impl Induction<'_> {
+    ///The two-part MDL of the examples under `entries`, or `None` if they are not all parseable.
+    fn score(&self, entries: &[Entry]) -> Option<(PyLexicon, f64)> {
+        let lexicon = SelfOwningLexicon::new(entries.iter().join("\n"), Some(false)).ok()?;
+        let lexicon = PyLexicon::from_lexicon(lexicon).ok()?;
+        let mut corpus = 0.0;
+        for example in self.examples {
+            let log_prob = lexicon
+                .string_log_prob(example, self.category, &self.search)
+                .ok()?;
+            if log_prob == f64::NEG_INFINITY {
+                return None;
+            }
+            corpus += bits(log_prob);
+        }
+        let grammar = lexicon.mdl(self.n_phonemes).ok()?;
+        Some((lexicon, grammar + corpus))
+    }
+}
+
+#[pymethods]
+impl PyLexicon {
+    #[staticmethod]
+    #[allow(clippy::too_many_arguments)]
+    #[pyo3(signature = (strings, category, n_phonemes, search_budget=1000, seed=None, min_log_prob=None, move_prob=0.5, max_steps=64, n_beams=None))]
+    ///Learns a lexicon from example strings by minimising their two-part MDL (see
+    ///:meth:`python_mg.Lexicon.corpus_mdl`).
+    ///
+    ///The search starts from a lexicon which memorises every example and then repeatedly proposes
+    ///an edit: merging two categories, splitting a category in two, or editing the features of an
+    ///entry (changing what a selector selects or its direction, adding or removing a licensor and
+    ///its licensees, or removing an entry). An edit is kept if every example can still be parsed
+    ///and the two-part MDL goes down.
+    ///
+    ///Parameters
+    ///----------
+    ///strings : list[str]
+    ///    The example strings, with words delimited by spaces.
+    ///category : str
+    ///    The syntactic category of the examples.
+    ///n_phonemes : int
+    ///    The number of phonemes that are possible in the phonology of the grammar (e.g. how many
+    ///    letters)
+    ///search_budget : int, optional
+    ///    The number of edits to propose.
+    ///    Default is 1000.
+    ///seed : int or None, optional
+    ///    The seed of the random number generator. If None, a random seed is used.
+    ///    Default is None.
+    ///min_log_prob : float or None, optional
+    ///    Minimum log probability threshold for the parser to consider
+    ///    Default is None.
+    ///move_prob : float, optional
+    ///    Probability of preferring a move over a merge when parsing.
+    ///    Default is 0.5
+    ///max_steps : int or None, optional
+    ///    Maximum number of derivation steps. If None, will not be limited.
+    ///    Default is 64.
+    ///n_beams : int or None, optional
+    ///    Number of beams to maintain while parsing. If None, will not be limited.
+    ///    Default is None.
+    ///
+    ///Returns
+    ///-------
+    ///tuple[Lexicon, list[float]]
+    ///    The best lexicon found and the two-part MDL of the search after each proposed edit
+    ///    (starting with the MDL of the initial lexicon).
+    ///
+    ///Raises
+    ///------
+    ///MGError
+    ///    If the examples contain affixed words or cannot be parsed by the initial lexicon.
+    fn induce(
+        strings: Vec<String>,
+        category: &str,
+        n_phonemes: u16,
+        search_budget: usize,
+        seed: Option<u64>,
+        min_log_prob: Option<f64>,
+        move_prob: f64,
+        max_steps: Option<usize>,
+        n_beams: Option<usize>,
+    ) -> PyResult<(PyLexicon, Vec<f64>)> {
+        if strings.iter().any(|x| x.contains('-')) {
+            return Err(MGError::new_err(
+                "Grammar induction does not support affixed words",
+            ));
+        }
+
+        let induction = Induction {
+            examples: &strings,
+            category,
+            n_phonemes,
+            search: get_config(min_log_prob, move_prob, max_steps, n_beams)?.into(),
+        };
+        let mut rng = StdRng::seed_from_u64(seed.unwrap_or_else(rand::random));
+
+        let mut entries = trivial_lexicon(&strings, category);
+        let (mut best, mut best_score) = induction.score(&entries).ok_or_else(|| {
+            MGError::new_err("The examples cannot be parsed by the initial lexicon")
+        })?;
+        let mut trajectory = vec![best_score];
+
+        for _ in 0..search_budget {
+            let proposal = match rng.random_range(0..3) {
+                0 => merge(&entries, category, &mut rng),
+                1 => split(&entries, &mut rng),
+                _ => edit_features(&entries, &mut rng),
+            };
+
+            if let Some(proposal) = proposal {
+                let proposal: Vec<Entry> = proposal.into_iter().unique().collect();
+                if let Some((lexicon, score)) = induction.score(&proposal)
+                    && score < best_score
+                {
+                    entries = proposal;
+                    best = lexicon;
+                    best_score = score;
+                }
+            }
+            trajectory.push(best_score);
+        }
+
+        Ok((best, trajectory))
+    }
+}
@@ -0,0 +1,247 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use logprob::LogProb;
+use minimalist_grammar_parser::RulePool;
+use pyo3::prelude::*;
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
+
+use crate::{
+    PyLexicon,
+    entries::{Entry, Feature},
+    errors::MGError,
+    map_string,
+    syntax::PySyntacticStructure,
+};
+
+///A lexical entry, split into its parts.
+#[derive(Debug, Serialize, Deserialize)]
+struct EntryJson {
+    lemma: Option<String>,
+    features: Vec<String>,
+    semantics: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    weight: Option<f64>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    metadata: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct LexiconJson {
+    semantic: bool,
+    entries: Vec<EntryJson>,
+    tokens: BTreeMap<String, usize>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct StructureJson {
+    string: Vec<String>,
+    log_prob: f64,
+    rules: RulePool,
+    lexicon: String,
+}
+
+fn from_json<T: DeserializeOwned>(s: &str) -> PyResult<T> {
+    serde_json::from_str(s).map_err(|e| MGError::new_err(format!("Invalid JSON: {e}")))
+}
+
+fn to_json<T: Serialize>(value: &T) -> String {
+    serde_json::to_string(value).expect("Serializing to a string cannot fail")
+}
+
+#[pymethods]
+impl PyLexicon {
+    ///Writes the lexicon as JSON, so that it can be saved and versioned outside of Python.
+    ///
+    ///Each entry is written with its lemma, features and semantic term (along with its weight, as
+    ///it was given, and metadata, if it has any), followed by the token IDs of the lexicon.
+    ///
+    ///Returns
+    ///-------
+    ///str
+    ///    The lexicon as a JSON object, which can be read with
+    ///    :meth:`python_mg.Lexicon.from_json`.
+    fn to_json(&self) -> String {
+        let weights = self.given_weights();
+        let entries = self
+            .entries
+            .iter()
+            .map(|entry| {
+                let key = entry.to_string();
+                EntryJson {
+                    lemma: entry.lemma.clone(),
+                    features: entry.features.iter().map(|x| x.to_string()).collect(),
+                    semantics: entry.semantics.clone(),
+                    weight: weights.as_ref().map(|weights| weights[&key]),
+                    metadata: self
+                        .metadata
+                        .get(&key)
+                        .map(|x| x.clone().into_iter().collect())
+                        .unwrap_or_default(),
+                }
+            })
+            .collect();
+        to_json(&LexiconJson {
+            semantic: self.is_semantic(),
+            entries,
+            tokens: self.word_id.ids().clone().into_iter().collect(),
+        })
+    }
+
+    #[staticmethod]
+    ///Reads a lexicon written by :meth:`python_mg.Lexicon.to_json`.
+    ///
+    ///Parameters
+    ///----------
+    ///s : str
+    ///    The JSON of the lexicon.
+    ///
+    ///Returns
+    ///-------
+    ///Lexicon
+    ///    The lexicon, equal to the one which was written.
+    ///
+    ///Raises
+    ///------
+    ///MGError
+    ///    If ``s`` is not the JSON of a lexicon, or an entry has an invalid feature.
+    ///GrammarSyntaxError
+    ///    If the entries do not make a valid lexicon.
+    fn from_json(s: &str) -> PyResult<PyLexicon> {
+        let data: LexiconJson = from_json(s)?;
+        let mut lines = vec![];
+        let mut weights = HashMap::new();
+        let mut metadata = HashMap::new();
+        for entry in data.entries {
+            let features = entry
+                .features
+                .iter()
+                .map(|x| {
+                    Feature::parse(x)
+                        .ok_or_else(|| MGError::new_err(format!("'{x}' is not a valid feature")))
+                })
+                .collect::<PyResult<Vec<_>>>()?;
+            let line = Entry {
+                lemma: entry.lemma,
+                features,
+                semantics: entry.semantics,
+            }
+            .to_string();
+            if let Some(weight) = entry.weight {
+                weights.insert(line.clone(), weight);
+            }
+            if !entry.metadata.is_empty() {
+                metadata.insert(line.clone(), entry.metadata.into_iter().collect());
+            }
+            lines.push(line);
+        }
+
+        PyLexicon::new(
+            lines.join("\n"),
+            Some(data.semantic),
+            (!weights.is_empty()).then_some(weights),
+            Some(data.tokens.into_iter().collect()),
+            Some(metadata),
+        )
+    }
+}
+
+///The structure of `lexicon` with the derivation `rules`.
+fn structure(
+    lexicon: &Bound<'_, PyLexicon>,
+    string: &[String],
+    log_prob: f64,
+    rules: RulePool,
+) -> PyResult<PySyntacticStructure> {
+    let known: HashSet<_> = lexicon.get().lexeme_to_id.values().collect();
+    if !rules.used_lemmas().all(|id| known.contains(&id)) {
+        return Err(MGError::new_err("The structure is of a different lexicon"));
+    }
+    let prob = LogProb::new(log_prob)
+        .map_err(|e| MGError::new_err(format!("Invalid log probability: {e}")))?;
+    let words = map_string(&string.join(" "));
+    Ok(PySyntacticStructure::into_syntax_structure(
+        lexicon, prob, &words, rules,
+    ))
+}
+
+#[pymethods]
+impl PySyntacticStructure {
+    ///Writes the structure as JSON, so that it can be saved outside of Python.
+    ///
+    ///The JSON has the string and log probability of the structure, the rules of its derivation
+    ///and the fingerprint of its lexicon, but not the lexicon itself, which must be saved
+    ///separately (e.g. with :meth:`python_mg.Lexicon.to_json`).
+    ///
+    ///Returns
+    ///-------
+    ///str
+    ///    The structure as a JSON object, which can be read with
+    ///    :meth:`python_mg.SyntacticStructure.from_json`.
+    fn to_json(&self) -> String {
+        to_json(&StructureJson {
+            string: self.string().iter().map(|x| x.to_string()).collect(),
+            log_prob: self.log_probability().into_inner(),
+            rules: self.rules().clone(),
+            lexicon: self.lex().get().fingerprint(),
+        })
+    }
+
+    ///Pickles the structure as the rules of its derivation along with its lexicon, which is
+    ///pickled by reference so that structures of the same lexicon share it.
+    #[allow(clippy::type_complexity)]
+    fn __reduce__<'py>(
+        &self,
+        py: Python<'py>,
+    ) -> PyResult<(Bound<'py, PyAny>, (Py<PyLexicon>, Vec<String>, f64, String))> {
+        let from_rules = py
+            .get_type::<PySyntacticStructure>()
+            .getattr("_from_rules")?;
+        Ok((
+            from_rules,
+            (
+                self.lex().clone_ref(py),
+                self.string().iter().map(|x| x.to_string()).collect(),
+                self.log_probability().into_inner(),
+                to_json(self.rules()),
+            ),
+        ))
+    }
+
+    #[staticmethod]
+    ///Unpickles a structure pickled by ``__reduce__``.
+    fn _from_rules(
+        lexicon: &Bound<'_, PyLexicon>,
+        string: Vec<String>,
+        log_prob: f64,
+        rules: &str,
+    ) -> PyResult<PySyntacticStructure> {
+        structure(lexicon, &string, log_prob, from_json(rules)?)
+    }
+
+    #[staticmethod]
+    ///Reads a structure written by :meth:`python_mg.SyntacticStructure.to_json`.
+    ///
+    ///Parameters
+    ///----------
+    ///s : str
+    ///    The JSON of the structure.
+    ///lexicon : Lexicon
+    ///    The lexicon of the structure.
+    ///
+    ///Returns
+    ///-------
+    ///SyntacticStructure
+    ///    The structure, equal to the one which was written.
+    ///
+    ///Raises
+    ///------
+    ///MGError
+    ///    If ``s`` is not the JSON of a structure, or if the structure is of a different lexicon.
+    fn from_json(s: &str, lexicon: &Bound<'_, PyLexicon>) -> PyResult<PySyntacticStructure> {
+        let data: StructureJson = from_json(s)?;
+        if data.lexicon != lexicon.get().fingerprint() {
+            return Err(MGError::new_err("The structure is of a different lexicon"));
+        }
+        structure(lexicon, &data.string, data.log_prob, data.rules)
+    }
+}
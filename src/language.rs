@@ -0,0 +1,306 @@
+use std::collections::BTreeSet;
+
+use minimalist_grammar_parser::parsing::beam::Continuation;
+use pyo3::prelude::*;
+
+use crate::{PyLexicon, PySyntacticStructure, map_string, weights::Search};
+
+///A string which one lexicon accepts and another does not.
+#[pyclass(name = "Counterexample", module = "python_mg", frozen)]
+pub struct PyCounterexample {
+    string: String,
+    parses: Vec<Py<PySyntacticStructure>>,
+    accepted_by: Py<PyLexicon>,
+}
+
+#[pymethods]
+impl PyCounterexample {
+    ///The counterexample, with words delimited by spaces.
+    #[getter]
+    fn string(&self) -> &str {
+        &self.string
+    }
+
+    ///The parses of the counterexample by the lexicon which accepts it.
+    #[getter]
+    fn parses(&self, py: Python<'_>) -> Vec<Py<PySyntacticStructure>> {
+        self.parses.iter().map(|x| x.clone_ref(py)).collect()
+    }
+
+    ///The lexicon which accepts the counterexample.
+    #[getter]
+    fn accepted_by(&self, py: Python<'_>) -> Py<PyLexicon> {
+        self.accepted_by.clone_ref(py)
+    }
+
+    fn __repr__(&self) -> String {
+        format!("Counterexample({:?})", self.string)
+    }
+}
+
+impl PyLexicon {
+    ///The words which can follow `prefix`, and whether `prefix` is itself a string of the lexicon.
+    pub(crate) fn next_words(
+        &self,
+        category: &str,
+        prefix: &[String],
+        search: &Search,
+    ) -> PyResult<(BTreeSet<String>, bool)> {
+        let prefix = prefix.join(" ");
+        let mut words = BTreeSet::new();
+        let mut ends = false;
+        for continuation in self.continuations_of(category, &map_string(&prefix), search)? {
+            match continuation {
+                Continuation::Word(word) => {
+                    words.insert(word);
+                }
+                Continuation::AffixedWord(items) => {
+                    words.insert(items.join("-"));
+                }
+                Continuation::EndOfSentence => ends = true,
+            }
+        }
+        Ok((words, ends))
+    }
+
+    ///Whether every word (or part of an affixed word) of `s` is in the lexicon.
+    fn knows_words(&self, s: &[String]) -> bool {
+        s.iter()
+            .flat_map(|word| word.split('-'))
+            .all(|word| self.word_id.get(word).is_some())
+    }
+}
+
+///Searches the strings of at most `max_len` words, shortest first, for one which `a` accepts and
+///`b` does not (or, if `symmetric`, the other way around as well). Each candidate is passed to
+///`check` along with whether it was `a` which accepted it, which returns the counterexample if it
+///really is one.
+fn search<T>(
+    a: &PyLexicon,
+    b: &PyLexicon,
+    category: &str,
+    max_len: usize,
+    symmetric: bool,
+    search_a: &Search,
+    search_b: &Search,
+    mut check: impl FnMut(&[String], bool) -> PyResult<Option<T>>,
+) -> PyResult<Option<T>> {
+    let mut frontier: Vec<(Vec<String>, bool, bool)> = vec![(vec![], true, true)];
+    for length in 0..=max_len {
+        let mut next = vec![];
+        for (prefix, in_a, in_b) in frontier {
+            let (words_a, ends_a) = if in_a {
+                a.next_words(category, &prefix, search_a)?
+            } else {
+                Default::default()
+            };
+            let (words_b, ends_b) = if in_b {
+                b.next_words(category, &prefix, search_b)?
+            } else {
+                Default::default()
+            };
+
+            if ends_a
+                && !ends_b
+                && let Some(x) = check(&prefix, true)?
+            {
+                return Ok(Some(x));
+            }
+            if symmetric
+                && ends_b
+                && !ends_a
+                && let Some(x) = check(&prefix, false)?
+            {
+                return Ok(Some(x));
+            }
+
+            if length == max_len {
+                continue;
+            }
+            for word in words_a.union(&words_b) {
+                let in_a = words_a.contains(word);
+                if in_a || symmetric {
+                    let mut prefix = prefix.clone();
+                    prefix.push(word.clone());
+                    next.push((prefix, in_a, words_b.contains(word)));
+                }
+            }
+        }
+        frontier = next;
+    }
+    Ok(None)
+}
+
+///Finds the shortest string accepted by `a` and not `b` (or, if `symmetric`, vice versa),
+///checking it by parsing it with both lexicons.
+#[allow(clippy::too_many_arguments)]
+fn counterexample(
+    a: &Bound<'_, PyLexicon>,
+    b: &Bound<'_, PyLexicon>,
+    category: &str,
+    max_len: usize,
+    symmetric: bool,
+    min_log_prob: Option<f64>,
+    move_prob: f64,
+    max_steps: Option<usize>,
+    n_beams: Option<usize>,
+) -> PyResult<Option<PyCounterexample>> {
+    let search_a = a
+        .get()
+        .search(min_log_prob, move_prob, max_steps, n_beams)?;
+    let search_b = b
+        .get()
+        .search(min_log_prob, move_prob, max_steps, n_beams)?;
+    let py = a.py();
+    let parse = |lexicon: &Bound<'_, PyLexicon>, s: &str, max_parses: Option<usize>| {
+        PyLexicon::inner_parse(
+            lexicon,
+            &map_string(s),
+            category.to_string(),
+            min_log_prob,
+            move_prob,
+            max_steps,
+            n_beams,
+            max_parses,
+        )
+    };
+
+    search(
+        a.get(),
+        b.get(),
+        category,
+        max_len,
+        symmetric,
+        &search_a,
+        &search_b,
+        |s, accepted_by_a| {
+            let (acceptor, rejector) = if accepted_by_a { (a, b) } else { (b, a) };
+            let string = s.join(" ");
+            if rejector.get().knows_words(s) && !parse(rejector, &string, Some(1))?.is_empty() {
+                return Ok(None);
+            }
+            let parses = parse(acceptor, &string, None)?;
+            if parses.is_empty() {
+                return Ok(None);
+            }
+            Ok(Some(PyCounterexample {
+                string,
+                parses: parses
+                    .into_iter()
+                    .map(|x| Py::new(py, x))
+                    .collect::<PyResult<_>>()?,
+                accepted_by: acceptor.clone().unbind(),
+            }))
+        },
+    )
+}
+
+#[pymethods]
+impl PyLexicon {
+    #[allow(clippy::too_many_arguments)]
+    #[pyo3(signature = (other, category, max_len, min_log_prob=None, move_prob=0.5, max_steps=64, n_beams=None))]
+    ///Checks whether two lexicons generate the same strings of up to ``max_len`` words.
+    ///
+    ///Parameters
+    ///----------
+    ///other : Lexicon
+    ///    The lexicon to compare to.
+    ///category : str
+    ///    The syntactic category of the strings.
+    ///max_len : int
+    ///    The maximum number of words of the strings to check.
+    ///min_log_prob : float or None, optional
+    ///    Minimum log probability threshold for the parser to consider
+    ///    Default is None.
+    ///move_prob : float, optional
+    ///    Probability of preferring a move over a merge when parsing.
+    ///    Default is 0.5
+    ///max_steps : int or None, optional
+    ///    Maximum number of derivation steps. If None, will not be limited.
+    ///    Default is 64.
+    ///n_beams : int or None, optional
+    ///    Number of beams to maintain while parsing. If None, will not be limited.
+    ///    Default is None.
+    ///
+    ///Returns
+    ///-------
+    ///Counterexample or None
+    ///    None if the lexicons generate the same strings, otherwise one of the shortest strings
+    ///    which only one of them generates.
+    fn equivalent_to(
+        slf: &Bound<'_, Self>,
+        other: &Bound<'_, PyLexicon>,
+        category: &str,
+        max_len: usize,
+        min_log_prob: Option<f64>,
+        move_prob: f64,
+        max_steps: Option<usize>,
+        n_beams: Option<usize>,
+    ) -> PyResult<Option<PyCounterexample>> {
+        counterexample(
+            slf,
+            other,
+            category,
+            max_len,
+            true,
+            min_log_prob,
+            move_prob,
+            max_steps,
+            n_beams,
+        )
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    #[pyo3(signature = (other, category, max_len, min_log_prob=None, move_prob=0.5, max_steps=64, n_beams=None))]
+    ///Checks whether this lexicon generates every string of up to ``max_len`` words that another
+    ///lexicon generates.
+    ///
+    ///Parameters
+    ///----------
+    ///other : Lexicon
+    ///    The lexicon whose strings should be included.
+    ///category : str
+    ///    The syntactic category of the strings.
+    ///max_len : int
+    ///    The maximum number of words of the strings to check.
+    ///min_log_prob : float or None, optional
+    ///    Minimum log probability threshold for the parser to consider
+    ///    Default is None.
+    ///move_prob : float, optional
+    ///    Probability of preferring a move over a merge when parsing.
+    ///    Default is 0.5
+    ///max_steps : int or None, optional
+    ///    Maximum number of derivation steps. If None, will not be limited.
+    ///    Default is 64.
+    ///n_beams : int or None, optional
+    ///    Number of beams to maintain while parsing. If None, will not be limited.
+    ///    Default is None.
+    ///
+    ///Returns
+    ///-------
+    ///Counterexample or None
+    ///    None if every string of ``other`` is generated by this lexicon, otherwise one of the
+    ///    shortest strings which ``other`` generates and this lexicon does not.
+    fn includes(
+        slf: &Bound<'_, Self>,
+        other: &Bound<'_, PyLexicon>,
+        category: &str,
+        max_len: usize,
+        min_log_prob: Option<f64>,
+        move_prob: f64,
+        max_steps: Option<usize>,
+        n_beams: Option<usize>,
+    ) -> PyResult<Option<PyCounterexample>> {
+        counterexample(
+            other,
+            slf,
+            category,
+            max_len,
+            false,
+            min_log_prob,
+            move_prob,
+            max_steps,
+            n_beams,
+        )
+    }
+}
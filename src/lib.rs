@@ -5,29 +5,69 @@ use std::{
 };
 
 use anyhow::anyhow;
+use itertools::Itertools;
 use logprob::LogProb;
 use minimalist_grammar_parser::{
     Generator, ParsingConfig, PhonContent, Pronounciation,
     lexicon::{LexemeId, LexicalEntry, Lexicon, SemanticLexicon},
     parsing::beam::Continuation,
 };
-use pyo3::{exceptions::PyValueError, prelude::*};
+use pyo3::prelude::*;
+use rand::{SeedableRng, rngs::StdRng};
 
 pub mod graphing;
 use graphing::{PyMgEdge, PyMgNode};
 
+mod automaton;
+mod compare;
+mod derivation;
+mod diff;
+mod entries;
+mod errors;
+mod feature_graph;
+mod files;
+mod fingerprint;
+mod induction;
+mod json;
+mod language;
+mod mcfg;
+mod mdl;
+mod memory_load;
+mod mutation;
+mod random;
+mod restrict;
 mod semantics;
 mod syntax;
+mod template;
 mod tokenizers;
+mod union;
+mod usage;
+mod weights;
 use syntax::PySyntacticStructure;
 
 use crate::{
+    automaton::PyAutomaton,
+    compare::PyLexiconComparison,
+    derivation::PyDerivationStep,
+    diff::{PyEntryChange, PyLexiconDiff},
+    entries::Entry,
+    errors::{GrammarError, LexiconError, MGError},
+    feature_graph::{PyFeatureEdge, PyFeatureGraph, PyFeatureNode},
+    language::PyCounterexample,
+    mcfg::{PyMcfg, PyMcfgRule},
+    mdl::PyMdlScore,
+    memory_load::PyMemoryLoadProfile,
+    random::RandomLexiconConfig,
     semantics::{
         PyMeaning, PyPossibleEvent, PyScenarioGenerator,
         lot_types::{PyActor, PyEvent},
         scenario::PyScenario,
     },
+    template::{PyGrammarTemplate, PyGrammarVariant},
     tokenizers::TokenMap,
+    union::PyLexiconUnion,
+    usage::PyExpectedUsage,
+    weights::{Search, Weights},
 };
 
 #[derive(Debug, Clone, Eq, PartialEq)]
@@ -46,13 +86,17 @@ impl Display for PossiblySemanticLexicon {
 }
 
 impl PossiblySemanticLexicon {
-    fn new(s: &'static str) -> anyhow::Result<Self> {
-        if let Ok(lex) = Lexicon::from_string(s) {
-            Ok(PossiblySemanticLexicon::Normal(lex))
+    ///Parses a lexicon, guessing whether it is semantic from whether any entry has a semantic term
+    ///if `semantic` is `None`.
+    fn new(s: &'static str, semantic: Option<bool>) -> Result<Self, GrammarError> {
+        if semantic.unwrap_or_else(|| entries::has_semantics(s)) {
+            SemanticLexicon::parse(s)
+                .map(PossiblySemanticLexicon::Semantic)
+                .map_err(|e| GrammarError::locate(s, true, e))
         } else {
-            Ok(PossiblySemanticLexicon::Semantic(SemanticLexicon::parse(
-                s,
-            )?))
+            Lexicon::from_string(s)
+                .map(PossiblySemanticLexicon::Normal)
+                .map_err(|e| GrammarError::locate(s, false, e))
         }
     }
 }
@@ -65,12 +109,12 @@ struct SelfOwningLexicon {
 }
 
 impl SelfOwningLexicon {
-    fn new(s: String) -> anyhow::Result<Self> {
+    fn new(s: String, semantic: Option<bool>) -> Result<Self, GrammarError> {
         let string = Arc::new(s);
         let str: &'static str = unsafe { std::mem::transmute(string.as_str()) };
 
         Ok(SelfOwningLexicon {
-            lexicon: PossiblySemanticLexicon::new(str)?,
+            lexicon: PossiblySemanticLexicon::new(str, semantic)?,
             string,
         })
     }
@@ -114,11 +158,40 @@ impl Display for SelfOwningLexicon {
 ///Parameters
 ///----------
 ///grammar : str
-///     
+///    The lexical entries of the grammar, one per line.
+///semantic : bool or None, optional
+///    Whether the entries have semantic interpretations. If None, the lexicon is semantic if any
+///    line has a second ``::`` (as in ``John::d::a_John``), so it must be given for lemmas which
+///    contain ``::``.
+///    Default is None.
+///weights : dict[str, float] or None, optional
+///    The weight of lexical entries, keyed by the entry. Weights can also be written at the end of
+///    an entry in the grammar, as in ``John::d @ 2.0``; weights given here take precedence. Weights
+///    are normalised between entries of the same category and unweighted entries have a weight of
+///    1. Entries with a weight of 0 are removed. If no entry has a weight, every choice of entry is
+///    equally likely. Entries which only differ in their semantics are the same lexeme to the
+///    parser, which uses the sum of their probabilities. Parsing and generation with a weighted
+///    lexicon rank and bound derivations by their weighted probability, do not support
+///    ``n_beams`` and need a ``max_steps`` which is not None.
+///    Default is None.
+///tokens : dict[str, int] or None, optional
+///    The token ID of each word, as returned by :meth:`python_mg.Lexicon.tokens`, so that the
+///    token IDs of several lexicons can be kept aligned. It must include every word of the grammar
+///    and may include words which are not in the grammar. If None, the IDs are assigned in order.
+///    Default is None.
+///metadata : dict[str, dict[str, str]] or None, optional
+///    Metadata of lexical entries (e.g. ``{"John::d": {"gloss": "John"}}``), keyed by the entry
+///    as for ``weights``. See :meth:`python_mg.Lexicon.metadata`.
+///    Default is None.
+///
 ///Raises
 ///------
-///ValueError
-///    If the string is not a valid lexicon.
+///GrammarSyntaxError
+///    If the string is not a valid lexicon. The error has the ``line``, ``column`` and ``entry``
+///    of the problem, as well as which ``parser`` (``"syntax"`` or ``"lot"``) rejected it.
+///MGError
+///    If ``tokens`` is missing a word or does not give every token a distinct ID, or if a key of
+///    ``weights`` or ``metadata`` is not an entry of the grammar.
 ///
 ///Examples
 ///--------
@@ -163,6 +236,9 @@ struct PyLexicon {
     word_id: TokenMap,
     lexeme_to_id: HashMap<LexicalEntry<&'static str, &'static str>, LexemeId>,
     lemma_to_id: HashMap<Pronounciation<&'static str>, Vec<LexemeId>>,
+    entries: Vec<Entry>,
+    weights: Option<Weights>,
+    metadata: HashMap<String, HashMap<String, String>>,
 
     //Has to be last bc of the static strs elsewhere
     lexicon: SelfOwningLexicon,
@@ -187,6 +263,7 @@ impl PyLexicon {
 #[pyclass]
 struct GrammarIterator {
     generator: Generator<Lexicon<String, String>, String, String>,
+    search: Search,
     max_strings: Option<usize>,
     n_strings: usize,
     lexicon: Py<PyLexicon>,
@@ -198,25 +275,31 @@ impl GrammarIterator {
         slf
     }
 
-    fn __next__(mut slf: PyRefMut<'_, Self>) -> Option<PySyntacticStructure> {
+    fn __next__(mut slf: PyRefMut<'_, Self>) -> PyResult<Option<PySyntacticStructure>> {
         if let Some(n) = slf.max_strings
             && slf.n_strings >= n
         {
-            return None;
+            return Ok(None);
         }
 
-        if let Some((prob, string, rules)) = slf.generator.next() {
+        while let Some((prob, string, rules)) = slf.generator.next() {
+            let Some(prob) = slf
+                .lexicon
+                .get()
+                .reweight_within(&slf.search, prob, &rules)?
+            else {
+                continue;
+            };
             slf.n_strings += 1;
             let py = slf.py();
-            Some(PySyntacticStructure::new(
+            return Ok(Some(PySyntacticStructure::new(
                 slf.lexicon.clone_ref(py),
                 prob,
                 string,
                 rules,
-            ))
-        } else {
-            None
+            )));
         }
+        Ok(None)
     }
 }
 
@@ -310,6 +393,13 @@ fn map_string(s: &str) -> Vec<PhonContent<&str>> {
     }
 }
 
+fn phon_content_to_string(x: &PhonContent<&str>) -> String {
+    match x {
+        PhonContent::Normal(s) => s.to_string(),
+        PhonContent::Affixed(items) => items.join("-"),
+    }
+}
+
 fn get_config(
     min_log_prob: Option<f64>,
     move_prob: f64,
@@ -333,14 +423,14 @@ fn get_config(
 }
 
 impl PyLexicon {
-    fn from_lexicon(lexicon: SelfOwningLexicon) -> PyResult<Self> {
+    fn from_lexicon(lexicon: SelfOwningLexicon) -> Result<Self, LexiconError> {
         //unsafe here because the lexicon has the lifetime of the reference of the SelfOwningLexicon.
         //We are owning it in the arc, so we have to make sure we can refer to it.
 
         let lexeme_to_id: HashMap<_, LexemeId> = lexicon
             .lexicon()
             .lexemes_and_ids()
-            .map_err(|e| anyhow!(e))?
+            .map_err(|e| PyErr::from(anyhow!(e)))?
             .map(|(id, entry)| {
                 let entry: LexicalEntry<&'static str, &'static str> =
                     unsafe { std::mem::transmute(entry) };
@@ -365,13 +455,127 @@ impl PyLexicon {
             lemma_to_id.entry(lemma).or_insert(vec![]).push(leaf);
         }
 
-        Ok(PyLexicon {
+        let entries = lexicon
+            .string
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(Entry::parse)
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| {
+                let semantic = matches!(lexicon.lexicon, PossiblySemanticLexicon::Semantic(_));
+                GrammarError::locate(&lexicon.string, semantic, e.message)
+            })?;
+
+        let lexicon = PyLexicon {
             lexicon,
             word_id,
             lexeme_to_id,
             lemma_to_id,
+            entries,
+            weights: None,
+            metadata: HashMap::new(),
+        };
+        //The entries are read by both parsers, so make sure that they agree on each of them.
+        for entry in &lexicon.entries {
+            lexicon.lexeme_id(entry).map_err(|_| {
+                MGError::new_err(format!(
+                    "'{entry}' was read differently by the grammar parser"
+                ))
+            })?;
+        }
+        Ok(lexicon)
+    }
+
+    ///Builds a lexicon as in [`PyLexicon::new`], keeping errors in the grammar structured.
+    fn build(
+        grammar: String,
+        semantic: Option<bool>,
+        weights: Option<HashMap<String, f64>>,
+        tokens: Option<HashMap<String, usize>>,
+        metadata: Option<HashMap<String, HashMap<String, String>>>,
+    ) -> Result<PyLexicon, LexiconError> {
+        let (grammar, weights) = weights::split_weights(&grammar, weights)?;
+        let mut lexicon = PyLexicon::from_lexicon(SelfOwningLexicon::new(grammar, semantic)?)?;
+        if let Some(tokens) = tokens {
+            lexicon.word_id = lexicon.word_id.with_ids(tokens)?;
+        }
+        if let Some(metadata) = metadata {
+            lexicon.metadata = files::match_metadata(&lexicon.entries, metadata)?;
+        }
+        Ok(match weights {
+            Some(weights) => lexicon.with_weights(&weights)?,
+            None => lexicon,
         })
     }
+
+    ///The lexeme of an entry of the lexicon.
+    fn lexeme_id(&self, entry: &Entry) -> PyResult<LexemeId> {
+        let syntax = entry.syntax();
+        let lexical_entry = LexicalEntry::parse(syntax.as_str())
+            .map_err(|e| GrammarError::locate(&syntax, false, e))?;
+        self.lexeme_to_id
+            .get(&lexical_entry)
+            .copied()
+            .ok_or_else(|| MGError::new_err(format!("'{syntax}' is not an entry of the lexicon")))
+    }
+
+    ///Weights each of the entries of the lexicon (see [`Weights`]).
+    fn with_weights(mut self, weights: &[f64]) -> PyResult<Self> {
+        let ids = self
+            .entries
+            .iter()
+            .map(|x| self.lexeme_id(x))
+            .collect::<PyResult<Vec<_>>>()?;
+        self.weights = Some(Weights::new(&self.entries, weights, &ids)?);
+        Ok(self)
+    }
+
+    ///The marginal log probability of each string generated by the lexicon, summing over all of
+    ///its derivations.
+    fn string_distribution<'a>(
+        &'a self,
+        category: &str,
+        search: &Search,
+        max_strings: Option<usize>,
+    ) -> PyResult<HashMap<Vec<PhonContent<&'a str>>, LogProb<f64>>> {
+        let mut hashmap = HashMap::new();
+        for (prob, string, rules) in self
+            .lexicon
+            .lexicon()
+            .generate(category, &search.config)
+            .map_err(|e| MGError::new_err(e.to_string()))?
+        {
+            let Some(prob) = self.reweight_within(search, prob, &rules)? else {
+                continue;
+            };
+            hashmap
+                .entry(string)
+                .and_modify(|old_log_prob: &mut LogProb<f64>| {
+                    *old_log_prob = old_log_prob.add_log_prob_clamped(prob);
+                })
+                .or_insert(prob);
+
+            if let Some(max_strings) = max_strings
+                && self.weights.is_none()
+                && hashmap.len() > max_strings
+            {
+                break;
+            }
+        }
+
+        //The parser finds strings in order of their unweighted probability, so the most probable
+        //strings of a weighted lexicon are only known once every string has been found.
+        if let Some(max_strings) = max_strings
+            && self.weights.is_some()
+        {
+            hashmap = hashmap
+                .into_iter()
+                .sorted_by_key(|(_, prob)| std::cmp::Reverse(*prob))
+                .take(max_strings)
+                .collect();
+        }
+        Ok(hashmap)
+    }
 }
 
 impl PyLexicon {
@@ -387,29 +591,41 @@ impl PyLexicon {
         max_parses: Option<usize>,
     ) -> PyResult<Vec<PySyntacticStructure>> {
         let lex = slf.borrow();
-        let config = get_config(min_log_prob, move_prob, max_steps, n_beams)?;
+        let search = lex.search(min_log_prob, move_prob, max_steps, n_beams)?;
         let parser = lex
             .lexicon
             .lexicon()
-            .parse(s, category.as_str(), &config)
-            .map_err(|e| PyValueError::new_err(e.to_string()))?;
+            .parse(s, category.as_str(), &search.config)
+            .map_err(|e| MGError::new_err(e.to_string()))?;
 
         //     let self_ref: Py<Self> = slf.clone().into_pyobject(py).unwrap().into();
 
+        if lex.weights.is_none() {
+            let parser = parser.map(|(prob, string, rules)| {
+                PySyntacticStructure::into_syntax_structure(slf, prob, string, rules)
+            });
+            return Ok(if let Some(max_parses) = max_parses {
+                parser.take(max_parses).collect()
+            } else {
+                parser.collect()
+            });
+        }
+
+        //The parser ranks parses without the weights, so every parse is needed to find the most
+        //probable ones.
+        let mut parses = vec![];
+        for (prob, string, rules) in parser {
+            if let Some(prob) = lex.reweight_within(&search, prob, &rules)? {
+                parses.push(PySyntacticStructure::into_syntax_structure(
+                    slf, prob, string, rules,
+                ));
+            }
+        }
+        parses.sort_by_key(|x| std::cmp::Reverse(x.log_probability()));
         if let Some(max_parses) = max_parses {
-            Ok(parser
-                .take(max_parses)
-                .map(|(prob, string, rules)| {
-                    PySyntacticStructure::into_syntax_structure(slf, prob, string, rules)
-                })
-                .collect())
-        } else {
-            Ok(parser
-                .map(|(prob, string, rules)| {
-                    PySyntacticStructure::into_syntax_structure(slf, prob, string, rules)
-                })
-                .collect())
+            parses.truncate(max_parses);
         }
+        Ok(parses)
     }
 }
 
@@ -420,8 +636,40 @@ impl PyLexicon {
         matches!(self.lexicon.lexicon, PossiblySemanticLexicon::Semantic(_))
     }
 
-    fn __getnewargs__(&self) -> (String,) {
-        (self.lexicon.to_string(),)
+    #[allow(clippy::type_complexity)]
+    fn __getnewargs__(
+        &self,
+    ) -> (
+        String,
+        bool,
+        Option<HashMap<String, f64>>,
+        HashMap<String, usize>,
+        HashMap<String, HashMap<String, String>>,
+    ) {
+        (
+            self.lexicon.to_string(),
+            self.is_semantic(),
+            self.given_weights(),
+            self.word_id.ids().clone(),
+            self.metadata.clone(),
+        )
+    }
+
+    ///The probability of each lexical entry, if the lexicon is weighted.
+    ///
+    ///Returns
+    ///-------
+    ///dict[str, float] or None
+    ///    The probability of each entry among the entries of its category, or None if the lexicon
+    ///    has no weights.
+    fn weights(&self) -> Option<HashMap<String, f64>> {
+        self.weights.as_ref().map(|weights| {
+            self.entries
+                .iter()
+                .zip(weights.entries())
+                .map(|(entry, prob)| (entry.to_string(), prob.into_inner().exp()))
+                .collect()
+        })
     }
 
     ///Gets the model description length of this lexicon. The precise calculation is described in `Deconstructing syntactic generalizations with minimalist grammars <https://aclanthology.org/2021.conll-1.34/>`_ (Ermolaeva, CoNLL 2021)
@@ -440,7 +688,7 @@ impl PyLexicon {
         self.lexicon
             .lexicon()
             .mdl_score(n_phonemes)
-            .map_err(|e| PyValueError::new_err(e.to_string()))
+            .map_err(|e| MGError::new_err(e.to_string()))
     }
 
     #[pyo3(signature = (prefix, category, min_log_prob=None, move_prob=0.5, max_steps=64, n_beams=None))]
@@ -478,40 +726,106 @@ impl PyLexicon {
         max_steps: Option<usize>,
         n_beams: Option<usize>,
     ) -> PyResult<HashSet<PyContinuation>> {
-        let config = get_config(min_log_prob, move_prob, max_steps, n_beams)?;
+        let search = self.search(min_log_prob, move_prob, max_steps, n_beams)?;
         let prefix = map_string(prefix);
 
         Ok(self
-            .lexicon
-            .lexicon()
-            .valid_continuations(&category.as_str(), &prefix, &config)
-            .map_err(|e| PyValueError::new_err(e.to_string()))?
+            .continuations_of(&category, &prefix, &search)?
             .into_iter()
-            .map(|x| {
-                PyContinuation(match x {
-                    Continuation::Word(x) => Continuation::Word(x.to_string()),
-                    Continuation::AffixedWord(items) => Continuation::AffixedWord(
-                        items.into_iter().map(|x| x.to_string()).collect(),
-                    ),
-                    Continuation::EndOfSentence => Continuation::EndOfSentence,
-                })
-            })
+            .map(PyContinuation)
             .collect())
     }
 
     #[staticmethod]
+    #[allow(clippy::too_many_arguments)]
+    #[pyo3(signature = (lemmas, seed=None, n_categories=3, n_licensees=1, head_movement_prob=0.0, max_features=4, start_category="0".to_string(), max_steps=64))]
     ///Generates a random lexicon with random categories.
     ///
+    ///The lexicon is guaranteed to generate at least one string of ``start_category`` with at most
+    ///one merge, whatever the number of categories.
+    ///
+    ///Parameters
+    ///----------
+    ///lemmas : list[str]
+    ///    The words of the lexicon. Each lemma gets one entry; if there are fewer lemmas than
+    ///    categories, the remaining categories get silent entries.
+    ///seed : int or None, optional
+    ///    The seed of the random number generator. If None, a random seed is used.
+    ///    Default is None.
+    ///n_categories : int, optional
+    ///    The number of categories (including the start category).
+    ///    Default is 3.
+    ///n_licensees : int, optional
+    ///    The number of licensee features, i.e. the number of kinds of movement.
+    ///    Default is 1.
+    ///head_movement_prob : float, optional
+    ///    The probability that a complement is selected with head movement.
+    ///    Default is 0.0.
+    ///max_features : int, optional
+    ///    The maximum number of features of each entry (including its category).
+    ///    Default is 4.
+    ///start_category : str, optional
+    ///    The category which the lexicon must be able to generate.
+    ///    Default is ``"0"``.
+    ///max_steps : int or None, optional
+    ///    Maximum number of derivation steps when checking the lexicon generates a string.
+    ///    Default is 64.
+    ///
     ///Returns
     ///-------
     ///:meth:`python_mg.Lexicon`
     ///    a random Lexicon
-    fn random_lexicon(lemmas: Vec<String>) -> PyResult<Self> {
-        let mut rng = rand::rng();
-        let lexicon: Lexicon<_, u16> = Lexicon::random(&0, &lemmas, None, &mut rng);
-        let lexicon = lexicon.remap_lexicon(Clone::clone, ToString::to_string);
-        let lex_s = lexicon.to_string();
-        PyLexicon::from_lexicon(SelfOwningLexicon::new(lex_s)?)
+    ///
+    ///Raises
+    ///------
+    ///MGError
+    ///    If the lexicon cannot generate a string of ``start_category`` in ``max_steps`` steps,
+    ///    which only happens if ``max_steps`` is too small for a single merge.
+    fn random_lexicon(
+        lemmas: Vec<String>,
+        seed: Option<u64>,
+        n_categories: usize,
+        n_licensees: usize,
+        head_movement_prob: f64,
+        max_features: usize,
+        start_category: String,
+        max_steps: Option<usize>,
+    ) -> PyResult<Self> {
+        if n_categories == 0 || max_features == 0 {
+            return Err(MGError::new_err(
+                "n_categories and max_features must be at least 1",
+            ));
+        }
+        if !(0.0..=1.0).contains(&head_movement_prob) {
+            return Err(MGError::new_err(
+                "head_movement_prob must be between 0 and 1",
+            ));
+        }
+
+        let mut rng = StdRng::seed_from_u64(seed.unwrap_or_else(rand::random));
+        let config = RandomLexiconConfig {
+            n_categories,
+            n_licensees,
+            head_movement_prob,
+            max_features,
+            start_category,
+        };
+        let parsing_config = get_config(None, 0.5, max_steps, None)?;
+
+        let grammar = config.sample(&lemmas, &mut rng).iter().join("\n");
+        let lexicon = SelfOwningLexicon::new(grammar, Some(false))?;
+        let generates = lexicon
+            .lexicon()
+            .generate(config.start_category.as_str(), &parsing_config)
+            .is_ok_and(|mut x| x.next().is_some());
+        if !generates {
+            return Err(MGError::new_err(format!(
+                "The sampled lexicon does not generate {} in {} steps",
+                config.start_category,
+                max_steps.map_or("unlimited".to_string(), |x| x.to_string())
+            )));
+        }
+        Ok(PyLexicon::from_lexicon(lexicon)?)
     }
 
     #[pyo3(signature = (category, min_log_prob=None, move_prob=0.5, max_steps=64, n_beams=None, max_strings=None))]
@@ -552,40 +866,17 @@ impl PyLexicon {
         n_beams: Option<usize>,
         max_strings: Option<usize>,
     ) -> PyResult<Vec<(Vec<String>, f64)>> {
-        let config = get_config(min_log_prob, move_prob, max_steps, n_beams)?;
-        let mut hashmap = HashMap::new();
-        for (prob, string, _) in self
-            .lexicon
-            .lexicon()
-            .generate(category, &config)
-            .map_err(|e| PyValueError::new_err(e.to_string()))?
-        {
-            hashmap
-                .entry(string)
-                .and_modify(|old_log_prob: &mut LogProb<f64>| {
-                    *old_log_prob = old_log_prob.add_log_prob_clamped(prob);
-                })
-                .or_insert(prob);
-
-            if let Some(max_strings) = max_strings
-                && hashmap.len() > max_strings
-            {
-                break;
-            }
-        }
-
-        let mut values = hashmap.into_iter().collect::<Vec<_>>();
+        let search = self.search(min_log_prob, move_prob, max_steps, n_beams)?;
+        let mut values = self
+            .string_distribution(category, &search, max_strings)?
+            .into_iter()
+            .collect::<Vec<_>>();
         values.sort_by_key(|x| x.1);
         Ok(values
             .into_iter()
             .map(|(s, p)| {
                 (
-                    s.into_iter()
-                        .map(|x| match x {
-                            PhonContent::Normal(s) => s.to_string(),
-                            PhonContent::Affixed(items) => items.join("-"),
-                        })
-                        .collect(),
+                    s.into_iter().map(|x| phon_content_to_string(&x)).collect(),
                     p.into_inner(),
                 )
             })
@@ -628,7 +919,7 @@ impl PyLexicon {
         n_beams: Option<usize>,
         max_strings: Option<usize>,
     ) -> PyResult<GrammarIterator> {
-        let config = get_config(min_log_prob, move_prob, max_steps, n_beams)?;
+        let search = slf.search(min_log_prob, move_prob, max_steps, n_beams)?;
 
         let py = slf.py();
         Ok(GrammarIterator {
@@ -637,8 +928,9 @@ impl PyLexicon {
                 .lexicon()
                 .clone()
                 .remap_lexicon(|x| x.to_string(), |y| y.to_string())
-                .into_generate(category, &config)
+                .into_generate(category, &search.config)
                 .map_err(|e| anyhow!(e))?,
+            search,
             max_strings,
             lexicon: slf.into_pyobject(py).unwrap().into(),
             n_strings: 0,
@@ -697,8 +989,17 @@ impl PyLexicon {
     }
 
     #[new]
-    fn new(grammar: String) -> PyResult<PyLexicon> {
-        PyLexicon::from_lexicon(SelfOwningLexicon::new(grammar)?)
+    #[pyo3(signature = (grammar, semantic=None, weights=None, tokens=None, metadata=None))]
+    fn new(
+        grammar: String,
+        semantic: Option<bool>,
+        weights: Option<HashMap<String, f64>>,
+        tokens: Option<HashMap<String, usize>>,
+        metadata: Option<HashMap<String, HashMap<String, String>>>,
+    ) -> PyResult<PyLexicon> {
+        Ok(PyLexicon::build(
+            grammar, semantic, weights, tokens, metadata,
+        )?)
     }
 }
 
@@ -717,5 +1018,23 @@ fn python_mg(m: &Bound<'_, PyModule>) -> PyResult<()> {
     m.add_class::<PyEvent>()?;
     m.add_class::<PyPossibleEvent>()?;
     m.add_class::<PyMeaning>()?;
+    m.add_class::<PyMdlScore>()?;
+    m.add_class::<PyExpectedUsage>()?;
+    m.add_class::<PyLexiconComparison>()?;
+    m.add_class::<PyCounterexample>()?;
+    m.add_class::<PyMcfg>()?;
+    m.add_class::<PyMcfgRule>()?;
+    m.add_class::<PyAutomaton>()?;
+    m.add_class::<PyFeatureGraph>()?;
+    m.add_class::<PyFeatureNode>()?;
+    m.add_class::<PyFeatureEdge>()?;
+    m.add_class::<PyGrammarTemplate>()?;
+    m.add_class::<PyGrammarVariant>()?;
+    m.add_class::<PyLexiconUnion>()?;
+    m.add_class::<PyLexiconDiff>()?;
+    m.add_class::<PyEntryChange>()?;
+    m.add_class::<PyDerivationStep>()?;
+    m.add_class::<PyMemoryLoadProfile>()?;
+    errors::add_exceptions(m)?;
     Ok(())
 }
@@ -0,0 +1,577 @@
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap, HashSet, VecDeque},
+    fmt::Display,
+};
+
+use itertools::Itertools;
+use pyo3::prelude::*;
+
+use crate::{
+    PyLexicon,
+    entries::{Entry, Feature, FeatureKind},
+};
+
+///The features left to check on a head and on each of the movers it is carrying. Under the
+///shortest move constraint there is at most one mover for each licensee, so movers are keyed by
+///their next licensee.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+struct State {
+    features: Vec<Feature>,
+    movers: BTreeMap<String, Vec<Feature>>,
+}
+
+impl Display for State {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "[{}", self.features.iter().join(" "))?;
+        if !self.movers.is_empty() {
+            write!(
+                f,
+                " | {}",
+                self.movers.values().map(|x| x.iter().join(" ")).join(", ")
+            )?;
+        }
+        write!(f, "]")
+    }
+}
+
+///The three components of the head chain: everything to the left of the head, the head and
+///everything to its right.
+const SPEC: usize = 0;
+const HEAD: usize = 1;
+const COMP: usize = 2;
+
+impl State {
+    fn lexical(entry: &Entry) -> Self {
+        State {
+            features: entry.features.clone(),
+            movers: BTreeMap::new(),
+        }
+    }
+
+    ///Whether the state is finished apart from moving, i.e. it only has a category and licensees.
+    fn is_complete(&self) -> bool {
+        self.features[0].kind == FeatureKind::Category
+            && self.features[1..]
+                .iter()
+                .all(|x| x.kind == FeatureKind::Licensee)
+    }
+
+    ///The component of a mover.
+    fn mover_component(&self, licensee: &str) -> usize {
+        3 + self.movers.keys().position(|x| x == licensee).unwrap()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+enum Nonterminal {
+    Start,
+    State(State),
+}
+
+impl Display for Nonterminal {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Nonterminal::Start => write!(f, "S"),
+            Nonterminal::State(state) => write!(f, "{state}"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+enum Symbol {
+    ///A component of one of the nonterminals on the right hand side of a rule.
+    Var(usize, usize),
+    Word(String),
+    ///Joins two morphemes into an affixed word (the result of head movement).
+    Affix,
+}
+
+impl Display for Symbol {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Symbol::Var(i, j) => write!(f, "x{i}_{j}"),
+            Symbol::Word(w) => write!(f, "{w}"),
+            Symbol::Affix => write!(f, "-"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct Rule {
+    lhs: Nonterminal,
+    rhs: Vec<State>,
+    components: Vec<Vec<Symbol>>,
+}
+
+fn var(child: usize, component: usize) -> Vec<Symbol> {
+    vec![Symbol::Var(child, component)]
+}
+
+fn chain(child: usize) -> Vec<Symbol> {
+    vec![
+        Symbol::Var(child, SPEC),
+        Symbol::Var(child, HEAD),
+        Symbol::Var(child, COMP),
+    ]
+}
+
+///Merges `arg` into `head`, or returns `None` if that would violate the shortest move
+///constraint.
+fn merge(head: &State, arg: &State) -> Option<Rule> {
+    let selector = &head.features[0];
+    let mut movers = head.movers.clone();
+    for (licensee, features) in &arg.movers {
+        if movers.insert(licensee.clone(), features.clone()).is_some() {
+            return None;
+        }
+    }
+
+    let licensees = &arg.features[1..];
+    let mut new_mover = None;
+    if let Some(licensee) = licensees.first() {
+        let is_affix = matches!(
+            selector.kind,
+            FeatureKind::LeftAffix | FeatureKind::RightAffix
+        );
+        if is_affix || movers.contains_key(&licensee.name) {
+            return None;
+        }
+        movers.insert(licensee.name.clone(), licensees.to_vec());
+        new_mover = Some(licensee.name.as_str());
+    }
+
+    let lhs = State {
+        features: head.features[1..].to_vec(),
+        movers,
+    };
+
+    let mut components = match (new_mover, selector.kind) {
+        (Some(_), _) => vec![var(0, SPEC), var(0, HEAD), var(0, COMP)],
+        (None, FeatureKind::RightSelector) => vec![
+            var(0, SPEC),
+            var(0, HEAD),
+            [var(0, COMP), chain(1)].concat(),
+        ],
+        (None, FeatureKind::LeftSelector) => vec![
+            [chain(1), var(0, SPEC)].concat(),
+            var(0, HEAD),
+            var(0, COMP),
+        ],
+        (None, FeatureKind::LeftAffix) => vec![
+            var(0, SPEC),
+            vec![Symbol::Var(1, HEAD), Symbol::Affix, Symbol::Var(0, HEAD)],
+            [var(0, COMP), var(1, SPEC), var(1, COMP)].concat(),
+        ],
+        (None, FeatureKind::RightAffix) => vec![
+            var(0, SPEC),
+            vec![Symbol::Var(0, HEAD), Symbol::Affix, Symbol::Var(1, HEAD)],
+            [var(0, COMP), var(1, SPEC), var(1, COMP)].concat(),
+        ],
+        (None, kind) => unreachable!("{kind:?} is not a selector"),
+    };
+
+    for licensee in lhs.movers.keys() {
+        components.push(if Some(licensee.as_str()) == new_mover {
+            chain(1)
+        } else if head.movers.contains_key(licensee) {
+            var(0, head.mover_component(licensee))
+        } else {
+            var(1, arg.mover_component(licensee))
+        });
+    }
+
+    Some(Rule {
+        lhs: Nonterminal::State(lhs),
+        rhs: vec![head.clone(), arg.clone()],
+        components,
+    })
+}
+
+///Moves the mover which `head` licenses, or returns `None` if there is no such mover or if moving
+///it would violate the shortest move constraint.
+fn move_mover(head: &State) -> Option<Rule> {
+    let licensor = &head.features[0].name;
+    let features = head.movers.get(licensor)?;
+    let mut movers = head.movers.clone();
+    movers.remove(licensor);
+
+    let remaining = &features[1..];
+    let still_moving = remaining.first().map(|x| x.name.clone());
+    if let Some(next) = &still_moving {
+        if movers.contains_key(next) {
+            return None;
+        }
+        movers.insert(next.clone(), remaining.to_vec());
+    }
+
+    let lhs = State {
+        features: head.features[1..].to_vec(),
+        movers,
+    };
+
+    let mover = head.mover_component(licensor);
+    let mut components = if still_moving.is_some() {
+        vec![var(0, SPEC), var(0, HEAD), var(0, COMP)]
+    } else {
+        vec![
+            vec![Symbol::Var(0, mover), Symbol::Var(0, SPEC)],
+            var(0, HEAD),
+            var(0, COMP),
+        ]
+    };
+    for licensee in lhs.movers.keys() {
+        components.push(if Some(licensee) == still_moving.as_ref() {
+            var(0, mover)
+        } else {
+            var(0, head.mover_component(licensee))
+        });
+    }
+
+    Some(Rule {
+        lhs: Nonterminal::State(lhs),
+        rhs: vec![head.clone()],
+        components,
+    })
+}
+
+///The lexical entries (by index) which can be the head of each state and each of its movers, and
+///the entries whose features each rule checks.
+#[derive(Debug, Default)]
+struct Provenance {
+    heads: HashMap<State, BTreeSet<usize>>,
+    movers: HashMap<(State, String), BTreeSet<usize>>,
+    rules: HashMap<Rule, BTreeSet<usize>>,
+}
+
+impl Provenance {
+    fn heads(&self, state: &State) -> BTreeSet<usize> {
+        self.heads.get(state).cloned().unwrap_or_default()
+    }
+
+    fn mover(&self, state: &State, licensee: &str) -> BTreeSet<usize> {
+        self.movers
+            .get(&(state.clone(), licensee.to_string()))
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    ///A lexical rule, whose lemma is `entry`.
+    fn lexical(&mut self, rule: &Rule, entry: usize) {
+        if let Nonterminal::State(state) = &rule.lhs {
+            self.heads.entry(state.clone()).or_default().insert(entry);
+        }
+        self.rules.entry(rule.clone()).or_default().insert(entry);
+    }
+
+    ///Passes the heads and movers of the right hand side of `rule` on to its left hand side and
+    ///records the entries that `rule` checks, returning whether anything new was found.
+    fn propagate(&mut self, rule: &Rule) -> bool {
+        let Nonterminal::State(lhs) = &rule.lhs else {
+            return false;
+        };
+        let (heads, movers): (BTreeSet<usize>, Vec<(String, BTreeSet<usize>)>) =
+            match rule.rhs.as_slice() {
+                [] => return false,
+                [head] => {
+                    let licensor = &head.features[0].name;
+                    let movers = lhs.movers.keys().map(|licensee| {
+                        let from = if licensee != licensor && head.movers.contains_key(licensee) {
+                            licensee
+                        } else {
+                            licensor
+                        };
+                        (licensee.clone(), self.mover(head, from))
+                    });
+                    (self.heads(head), movers.collect())
+                }
+                [head, arg, ..] => {
+                    let movers = lhs.movers.keys().map(|licensee| {
+                        let entries = if head.movers.contains_key(licensee) {
+                            self.mover(head, licensee)
+                        } else if arg.movers.contains_key(licensee) {
+                            self.mover(arg, licensee)
+                        } else {
+                            self.heads(arg)
+                        };
+                        (licensee.clone(), entries)
+                    });
+                    (self.heads(head), movers.collect())
+                }
+            };
+
+        let checked = self.checked(rule);
+        let rule_entries = self.rules.entry(rule.clone()).or_default();
+        let n = rule_entries.len();
+        rule_entries.extend(checked);
+        let mut changed = rule_entries.len() != n;
+
+        let lhs_heads = self.heads.entry(lhs.clone()).or_default();
+        let n = lhs_heads.len();
+        lhs_heads.extend(heads);
+        changed |= lhs_heads.len() != n;
+        for (licensee, entries) in movers {
+            let lhs_movers = self.movers.entry((lhs.clone(), licensee)).or_default();
+            let n = lhs_movers.len();
+            lhs_movers.extend(entries);
+            changed |= lhs_movers.len() != n;
+        }
+        changed
+    }
+
+    ///The entries whose features `rule` checks.
+    fn checked(&self, rule: &Rule) -> BTreeSet<usize> {
+        match (&rule.lhs, rule.rhs.as_slice()) {
+            (Nonterminal::Start, _) | (_, []) => BTreeSet::new(),
+            (_, [head]) => {
+                let mut entries = self.heads(head);
+                entries.extend(self.mover(head, &head.features[0].name));
+                entries
+            }
+            (_, rhs) => rhs.iter().flat_map(|x| self.heads(x)).collect(),
+        }
+    }
+}
+
+///Builds every rule which can be derived from the lexical entries, starting from the lexical
+///rules and applying merge and move until no new nonterminals are found. Each rule comes with the
+///indices of the entries whose features it checks.
+fn compile(entries: &[Entry], start: &str) -> Vec<(Rule, BTreeSet<usize>)> {
+    let mut rules: Vec<Rule> = vec![];
+    let mut provenance = Provenance::default();
+    let mut seen_rules: HashSet<Rule> = HashSet::new();
+    let mut states: BTreeSet<State> = BTreeSet::new();
+    let mut agenda: VecDeque<State> = VecDeque::new();
+
+    let mut add = |rule: Rule,
+                   rules: &mut Vec<Rule>,
+                   states: &mut BTreeSet<State>,
+                   agenda: &mut VecDeque<State>| {
+        if let Nonterminal::State(state) = &rule.lhs
+            && states.insert(state.clone())
+        {
+            agenda.push_back(state.clone());
+        }
+        if seen_rules.insert(rule.clone()) {
+            rules.push(rule);
+        }
+    };
+
+    for (i, entry) in entries.iter().enumerate() {
+        let rule = Rule {
+            lhs: Nonterminal::State(State::lexical(entry)),
+            rhs: vec![],
+            components: vec![
+                vec![],
+                entry
+                    .lemma
+                    .iter()
+                    .map(|x| Symbol::Word(x.clone()))
+                    .collect(),
+                vec![],
+            ],
+        };
+        provenance.lexical(&rule, i);
+        add(rule, &mut rules, &mut states, &mut agenda);
+    }
+
+    while let Some(state) = agenda.pop_front() {
+        let feature = &state.features[0];
+        let mut new_rules = vec![];
+        match feature.kind {
+            FeatureKind::Licensor => new_rules.extend(move_mover(&state)),
+            FeatureKind::Category => {
+                if !state.is_complete() {
+                    continue;
+                }
+                for head in &states {
+                    let selector = &head.features[0];
+                    if selector.kind.is_selector() && selector.name == feature.name {
+                        new_rules.extend(merge(head, &state));
+                    }
+                }
+                if state.features.len() == 1 && state.movers.is_empty() && feature.name == start {
+                    new_rules.push(Rule {
+                        lhs: Nonterminal::Start,
+                        rhs: vec![state.clone()],
+                        components: vec![chain(0)],
+                    });
+                }
+            }
+            _ => {
+                for arg in &states {
+                    if arg.is_complete() && arg.features[0].name == feature.name {
+                        new_rules.extend(merge(&state, arg));
+                    }
+                }
+            }
+        }
+        for rule in new_rules {
+            add(rule, &mut rules, &mut states, &mut agenda);
+        }
+    }
+
+    //Only keep the rules which can be used in a derivation of the start category.
+    let mut useful: HashSet<Nonterminal> = HashSet::from([Nonterminal::Start]);
+    loop {
+        let n = useful.len();
+        for rule in &rules {
+            if useful.contains(&rule.lhs) {
+                useful.extend(rule.rhs.iter().cloned().map(Nonterminal::State));
+            }
+        }
+        if useful.len() == n {
+            break;
+        }
+    }
+    rules.retain(|rule| useful.contains(&rule.lhs));
+
+    loop {
+        let mut changed = false;
+        for rule in &rules {
+            changed |= provenance.propagate(rule);
+        }
+        if !changed {
+            break;
+        }
+    }
+    rules
+        .into_iter()
+        .map(|rule| {
+            let checked = provenance.rules.remove(&rule).unwrap_or_default();
+            (rule, checked)
+        })
+        .collect()
+}
+
+///A rule of a multiple context-free grammar.
+#[pyclass(name = "MCFGRule", module = "python_mg", str, frozen, get_all)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PyMcfgRule {
+    ///The nonterminal on the left hand side of the rule.
+    lhs: String,
+    ///The nonterminals on the right hand side of the rule.
+    rhs: Vec<String>,
+    ///How each component of ``lhs`` is built, as a list of symbols. ``"x{i}_{j}"`` is the
+    ///``j``-th component of the ``i``-th nonterminal of ``rhs``, ``"-"`` joins two morphemes into
+    ///an affixed word and anything else is a word.
+    components: Vec<Vec<String>>,
+    ///The lexical entries whose features the rule checks.
+    entries: Vec<String>,
+}
+
+impl Display for PyMcfgRule {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let components = self.components.iter().map(|x| {
+            if x.is_empty() {
+                "ε".to_string()
+            } else {
+                //The morphemes of an affixed word are written without spaces, as in `x1_1-x0_1`.
+                x.join(" ").replace(" - ", "-")
+            }
+        });
+        write!(f, "{}({})", self.lhs, components.format(", "))?;
+        if self.rhs.is_empty() {
+            write!(f, ".")
+        } else {
+            write!(f, " :- {}", self.rhs.join(", "))
+        }
+    }
+}
+
+///A multiple context-free grammar which is weakly equivalent to a lexicon.
+#[pyclass(name = "MCFG", module = "python_mg", str, frozen, get_all)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PyMcfg {
+    ///The start symbol of the grammar.
+    start: String,
+    ///The rules of the grammar.
+    rules: Vec<PyMcfgRule>,
+}
+
+impl Display for PyMcfg {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.rules.iter().join("\n"))
+    }
+}
+
+#[pymethods]
+impl PyMcfg {
+    ///The grammar as text, with one rule per line. Rules are written as
+    ///``A(x0_0 x1_0, x0_1) :- B, C`` where ``x{i}_{j}`` is the ``j``-th component of the ``i``-th
+    ///nonterminal on the right, and lexical rules are written as ``A(ε, word, ε).`` The morphemes
+    ///of an affixed word are joined by ``-``, as in ``x1_1-x0_1``.
+    ///
+    ///Returns
+    ///-------
+    ///str
+    ///    The rules of the grammar.
+    fn to_text(&self) -> String {
+        self.to_string()
+    }
+
+    ///The number of components of each nonterminal of the grammar.
+    ///
+    ///Returns
+    ///-------
+    ///dict[str, int]
+    ///    The dimension of each nonterminal.
+    fn dimensions(&self) -> BTreeMap<String, usize> {
+        self.rules
+            .iter()
+            .map(|x| (x.lhs.clone(), x.components.len()))
+            .collect()
+    }
+
+    fn __repr__(&self) -> String {
+        format!("MCFG(start={:?}, n_rules={})", self.start, self.rules.len())
+    }
+}
+
+#[pymethods]
+impl PyLexicon {
+    ///Compiles the lexicon into a weakly equivalent multiple context-free grammar (MCFG).
+    ///
+    ///Each nonterminal is the features left to check on a head along with those of each of the
+    ///movers it carries, following Michaelis (2001) and Stabler & Keenan (2003). The head has three
+    ///components (what is to its left, itself and what is to its right) so that head movement can
+    ///be compiled, and each mover has one. With ``=>x`` the stolen head is attached to the left of
+    ///the selecting head, and with ``x<=`` to its right. A complement selected with head movement
+    ///cannot move itself.
+    ///
+    ///Parameters
+    ///----------
+    ///start_category : str
+    ///    The category of the strings of the grammar.
+    ///
+    ///Returns
+    ///-------
+    ///MCFG
+    ///    The grammar, keeping only rules that can be used to derive ``start_category``.
+    fn to_mcfg(&self, start_category: &str) -> PyMcfg {
+        let rules = compile(&self.entries, start_category);
+        let rules = rules
+            .into_iter()
+            .map(|(rule, checked)| {
+                let entries: BTreeSet<String> = checked
+                    .into_iter()
+                    .map(|i| self.entries[i].syntax())
+                    .collect();
+                PyMcfgRule {
+                    lhs: rule.lhs.to_string(),
+                    rhs: rule.rhs.iter().map(|x| x.to_string()).collect(),
+                    components: rule
+                        .components
+                        .iter()
+                        .map(|x| x.iter().map(|x| x.to_string()).collect())
+                        .collect(),
+                    entries: entries.into_iter().collect(),
+                }
+            })
+            .collect();
+
+        PyMcfg {
+            start: Nonterminal::Start.to_string(),
+            rules,
+        }
+    }
+}
@@ -0,0 +1,216 @@
+use std::collections::HashMap;
+
+use itertools::Itertools;
+
+use pyo3::prelude::*;
+
+use crate::{
+    PyLexicon, PySyntacticStructure, SelfOwningLexicon, entries::Entry, errors::MGError,
+    map_string, weights::Search,
+};
+
+///Adds probabilities in log space.
+pub(crate) fn log_sum_exp(log_probs: impl IntoIterator<Item = f64>) -> f64 {
+    let log_probs: Vec<f64> = log_probs.into_iter().collect();
+    let max = log_probs.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+    if max == f64::NEG_INFINITY {
+        return f64::NEG_INFINITY;
+    }
+    max + log_probs.iter().map(|x| (x - max).exp()).sum::<f64>().ln()
+}
+
+///Converts a natural log probability into its code length in bits.
+pub(crate) fn bits(log_prob: f64) -> f64 {
+    -log_prob / std::f64::consts::LN_2
+}
+
+#[derive(FromPyObject)]
+enum CorpusItem<'py> {
+    #[pyo3(transparent, annotation = "SyntacticStructure")]
+    Structure(Bound<'py, PySyntacticStructure>),
+    #[pyo3(transparent, annotation = "str")]
+    String(String),
+}
+
+///The two-part minimum description length of a corpus: the cost of the grammar plus the cost of
+///encoding the corpus with the grammar.
+#[pyclass(name = "MDLScore", module = "python_mg", frozen, get_all)]
+#[derive(Debug, Clone, PartialEq)]
+pub struct PyMdlScore {
+    ///The MDL of the lexicon itself, as in :meth:`python_mg.Lexicon.mdl`.
+    grammar: f64,
+    ///The number of bits needed to encode the corpus with the lexicon.
+    corpus: f64,
+    ///The sum of ``grammar`` and ``corpus``.
+    total: f64,
+    ///The number of bits needed to encode each item of the corpus. Strings that cannot be parsed
+    ///cost ``inf``.
+    per_item: Vec<f64>,
+}
+
+#[pymethods]
+impl PyMdlScore {
+    fn __repr__(&self) -> String {
+        format!(
+            "MDLScore(grammar={}, corpus={}, total={})",
+            self.grammar, self.corpus, self.total
+        )
+    }
+}
+
+impl PyLexicon {
+    ///The total log probability of all parses of `s`.
+    pub(crate) fn string_log_prob(
+        &self,
+        s: &str,
+        category: &str,
+        search: &Search,
+    ) -> PyResult<f64> {
+        let mut log_probs = vec![];
+        for (prob, _, rules) in self
+            .lexicon
+            .lexicon()
+            .parse(&map_string(s), category, &search.config)
+            .map_err(|e| MGError::new_err(e.to_string()))?
+        {
+            if let Some(prob) = self.reweight_within(search, prob, &rules)? {
+                log_probs.push(prob.into_inner());
+            }
+        }
+        Ok(log_sum_exp(log_probs))
+    }
+}
+
+#[pymethods]
+impl PyLexicon {
+    ///Splits the MDL of the lexicon (see :meth:`python_mg.Lexicon.mdl`) between its lexical
+    ///entries, so that the costs sum to the MDL of the lexicon.
+    ///
+    ///Each entry is charged in proportion to how much the MDL of the lexicon goes down without it,
+    ///as computed by :meth:`python_mg.Lexicon.mdl`. Phonemes and features thus cost what they cost
+    ///in the MDL of the lexicon.
+    ///
+    ///Parameters
+    ///----------
+    ///n_phonemes : int
+    ///    The number of phonemes that are possible in the phonology of the grammar (e.g. how many
+    ///    letters)
+    ///
+    ///Returns
+    ///-------
+    ///dict[str, float]
+    ///    The cost of each lexical entry, keyed by the entry (with its semantic term, if it has
+    ///    one). Entries which are written identically share a key and their costs are summed.
+    fn mdl_breakdown(&self, n_phonemes: u16) -> PyResult<HashMap<String, f64>> {
+        let total = self.mdl(n_phonemes)?;
+        let syntax: Vec<String> = self.entries.iter().map(Entry::syntax).collect();
+
+        let mut savings = vec![];
+        for i in 0..syntax.len() {
+            let rest = syntax
+                .iter()
+                .enumerate()
+                .filter(|(j, _)| *j != i)
+                .map(|(_, x)| x)
+                .join("\n");
+            let without = if rest.is_empty() {
+                0.0
+            } else {
+                SelfOwningLexicon::new(rest, Some(false))?
+                    .lexicon()
+                    .mdl_score(n_phonemes)
+                    .map_err(|e| MGError::new_err(e.to_string()))?
+            };
+            savings.push((total - without).max(0.0));
+        }
+
+        let total_savings: f64 = savings.iter().sum();
+        let mut costs = HashMap::new();
+        for (entry, saving) in self.entries.iter().zip(savings) {
+            let share = if total_savings > 0.0 {
+                saving / total_savings
+            } else {
+                1.0 / self.entries.len() as f64
+            };
+            *costs.entry(entry.to_string()).or_insert(0.0) += total * share;
+        }
+        Ok(costs)
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    #[pyo3(signature = (corpus, category, n_phonemes, min_log_prob=None, move_prob=0.5, max_steps=64, n_beams=None))]
+    ///Computes the two-part MDL of a corpus: the MDL of the lexicon plus the number of bits needed
+    ///to encode the corpus using the lexicon.
+    ///
+    ///A string costs the negative log probability (in bits) of all of its parses, while a
+    ///SyntacticStructure costs the negative log probability of its derivation, and must come from
+    ///this lexicon.
+    ///
+    ///Parameters
+    ///----------
+    ///corpus : list[str | SyntacticStructure]
+    ///    The strings (delimited as in :meth:`python_mg.Lexicon.parse`) or parses to encode.
+    ///category : str
+    ///    The syntactic category of the strings of the corpus.
+    ///n_phonemes : int
+    ///    The number of phonemes that are possible in the phonology of the grammar (e.g. how many
+    ///    letters)
+    ///min_log_prob : float or None, optional
+    ///    Minimum log probability threshold for the parser to consider
+    ///    Default is None.
+    ///move_prob : float, optional
+    ///    Probability of preferring a move over a merge when parsing.
+    ///    Default is 0.5
+    ///max_steps : int or None, optional
+    ///    Maximum number of derivation steps. If None, will not be limited.
+    ///    Default is 64.
+    ///n_beams : int or None, optional
+    ///    Number of beams to maintain while parsing. If None, will not be limited.
+    ///    Default is None.
+    ///
+    ///Returns
+    ///-------
+    ///MDLScore
+    ///    The cost of the grammar, the corpus and of each item in the corpus.
+    ///
+    ///Raises
+    ///------
+    ///MGError
+    ///    If a SyntacticStructure of the corpus was made by a different lexicon.
+    fn corpus_mdl<'py>(
+        &self,
+        corpus: Vec<CorpusItem<'py>>,
+        category: &str,
+        n_phonemes: u16,
+        min_log_prob: Option<f64>,
+        move_prob: f64,
+        max_steps: Option<usize>,
+        n_beams: Option<usize>,
+    ) -> PyResult<PyMdlScore> {
+        let search = self.search(min_log_prob, move_prob, max_steps, n_beams)?;
+        let per_item = corpus
+            .iter()
+            .map(|item| match item {
+                CorpusItem::Structure(structure) => {
+                    let structure = structure.get();
+                    if structure.lex().get() != self {
+                        return Err(MGError::new_err(
+                            "SyntacticStructure was made by a different lexicon",
+                        ));
+                    }
+                    Ok(bits(structure.log_probability().into_inner()))
+                }
+                CorpusItem::String(s) => Ok(bits(self.string_log_prob(s, category, &search)?)),
+            })
+            .collect::<PyResult<Vec<_>>>()?;
+
+        let grammar = self.mdl(n_phonemes)?;
+        let corpus: f64 = per_item.iter().sum();
+        Ok(PyMdlScore {
+            grammar,
+            corpus,
+            total: grammar + corpus,
+            per_item,
+        })
+    }
+}
@@ -0,0 +1,93 @@
+use pyo3::prelude::*;
+
+use crate::{derivation::Tree, syntax::PySyntacticStructure};
+
+///The memory load of a derivation over time, as returned by
+///:meth:`python_mg.SyntacticStructure.memory_load_profile`.
+///
+///Tenure and stack size follow the processing-complexity metrics of Kobele, Gerth and Hale (2013)
+///and Graf and Marcinek (2014): the tree is parsed top-down and from left to right (with movers
+///where they land), and each node is put on a stack when its parent is expanded.
+#[pyclass(name = "MemoryLoadProfile", module = "python_mg", frozen, get_all)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PyMemoryLoadProfile {
+    ///The number of movers in memory after each step of
+    ///:meth:`python_mg.SyntacticStructure.steps`.
+    movers: Vec<usize>,
+    ///The pronounced words, in the order they are said.
+    words: Vec<String>,
+    ///How many steps each word waited on the stack between being predicted and being read.
+    tenure: Vec<usize>,
+    ///How many nodes were left on the stack when each word was read.
+    stack_size: Vec<usize>,
+    ///The longest tenure of any node of the tree, pronounced or not.
+    max_tenure: usize,
+    ///The sum of the tenure of every node of the tree.
+    sum_tenure: usize,
+    ///The largest number of nodes on the stack at once.
+    max_stack_size: usize,
+}
+
+#[pymethods]
+impl PyMemoryLoadProfile {
+    fn __repr__(&self) -> String {
+        format!(
+            "MemoryLoadProfile(max_tenure={}, sum_tenure={}, max_stack_size={})",
+            self.max_tenure, self.sum_tenure, self.max_stack_size
+        )
+    }
+}
+
+#[pymethods]
+impl PySyntacticStructure {
+    ///The memory load of the derivation at each step, and of each word when the structure is
+    ///parsed top-down, for models of processing complexity.
+    ///
+    ///Returns
+    ///-------
+    ///MemoryLoadProfile
+    ///    The number of movers in memory after each step of the derivation, and the tenure and
+    ///    stack size of each word.
+    fn memory_load_profile(&self) -> PyResult<PyMemoryLoadProfile> {
+        let lexicon = self.lex().get();
+        let tree = Tree::new(lexicon, self.rules());
+        let movers = lexicon
+            .derivation_steps(self.rules())?
+            .iter()
+            .map(|x| x.memory.len())
+            .collect();
+
+        let mut profile = PyMemoryLoadProfile {
+            movers,
+            words: vec![],
+            tenure: vec![],
+            stack_size: vec![],
+            max_tenure: 0,
+            sum_tenure: 0,
+            max_stack_size: 0,
+        };
+        let mut stack = vec![(tree.root, 1)];
+        let mut step = 0;
+        while let Some((node, index)) = stack.pop() {
+            step += 1;
+            let tenure = step - index;
+            profile.max_tenure = profile.max_tenure.max(tenure);
+            profile.sum_tenure += tenure;
+
+            let children = &tree.children[node];
+            let (lemma, _) = tree.labels[node].split_once("::").unwrap_or_default();
+            if children.is_empty()
+                && tree.traces[node].is_none()
+                && !tree.stolen[node]
+                && !matches!(lemma, "" | "ε")
+            {
+                profile.words.push(lemma.to_string());
+                profile.tenure.push(tenure);
+                profile.stack_size.push(stack.len());
+            }
+            stack.extend(children.iter().rev().map(|child| (*child, step)));
+            profile.max_stack_size = profile.max_stack_size.max(stack.len());
+        }
+        Ok(profile)
+    }
+}
@@ -0,0 +1,263 @@
+use std::collections::BTreeSet;
+
+use itertools::Itertools;
+use pyo3::prelude::*;
+use rand::{prelude::*, rngs::StdRng};
+
+use crate::{
+    PyLexicon,
+    entries::{Entry, Feature, FeatureKind},
+    errors::MGError,
+};
+
+const OPERATIONS: [&str; 5] = [
+    "add_feature",
+    "remove_feature",
+    "duplicate_entry",
+    "swap_selectors",
+    "toggle_head_movement",
+];
+
+///The names of every feature of a kind (or of every category, for [`FeatureKind::Category`]).
+fn names(entries: &[Entry], kinds: &[FeatureKind]) -> Vec<String> {
+    entries
+        .iter()
+        .flat_map(|x| x.features.iter())
+        .filter(|x| kinds.contains(&x.kind))
+        .map(|x| x.name.clone())
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .collect()
+}
+
+///Adds a selector or licensor before the category of an entry, or a licensee after it.
+fn add_feature(entries: &mut [Entry], rng: &mut StdRng) -> Option<usize> {
+    let categories = names(entries, &[FeatureKind::Category]);
+    let licensees = names(entries, &[FeatureKind::Licensor, FeatureKind::Licensee]);
+    let i = (0..entries.len()).choose(rng)?;
+    let entry = &mut entries[i];
+    let category = entry
+        .features
+        .iter()
+        .position(|x| x.kind == FeatureKind::Category)?;
+
+    let kind = *[
+        FeatureKind::RightSelector,
+        FeatureKind::LeftSelector,
+        FeatureKind::Licensor,
+        FeatureKind::Licensee,
+    ]
+    .choose(rng)?;
+    let (name, position) = match kind {
+        FeatureKind::Licensor => (licensees.choose(rng)?, rng.random_range(0..=category)),
+        FeatureKind::Licensee => (
+            licensees.choose(rng)?,
+            rng.random_range(category + 1..=entry.features.len()),
+        ),
+        _ => (categories.choose(rng)?, rng.random_range(0..=category)),
+    };
+    entry
+        .features
+        .insert(position, Feature::new(kind, name.clone()));
+    Some(i)
+}
+
+///Removes a feature other than the category from an entry.
+fn remove_feature(entries: &mut [Entry], rng: &mut StdRng) -> Option<usize> {
+    let (i, j) = entries
+        .iter()
+        .enumerate()
+        .flat_map(|(i, x)| {
+            x.features
+                .iter()
+                .enumerate()
+                .filter(|(_, f)| f.kind != FeatureKind::Category)
+                .map(move |(j, _)| (i, j))
+        })
+        .choose(rng)?;
+    entries[i].features.remove(j);
+    Some(i)
+}
+
+///Swaps two selectors of an entry.
+fn swap_selectors(entries: &mut [Entry], rng: &mut StdRng) -> Option<usize> {
+    let selectors =
+        |x: &[Feature]| -> Vec<usize> { x.iter().positions(|f| f.kind.is_selector()).collect() };
+    let i = (0..entries.len())
+        .filter(|i| {
+            let features = &entries[*i].features;
+            selectors(features)
+                .iter()
+                .map(|j| &features[*j])
+                .unique()
+                .count()
+                > 1
+        })
+        .choose(rng)?;
+    let features = &mut entries[i].features;
+    let selectors = selectors(&*features);
+    let a = *selectors.choose(rng)?;
+    let b = *selectors
+        .iter()
+        .filter(|b| features[**b] != features[a])
+        .choose(rng)?;
+    features.swap(a, b);
+    Some(i)
+}
+
+///Turns a selector into one which does head movement, or vice versa (see
+///[`FeatureKind::toggle_head_movement`]).
+fn toggle_head_movement(entries: &mut [Entry], rng: &mut StdRng) -> Option<usize> {
+    let (i, j) = entries
+        .iter()
+        .enumerate()
+        .flat_map(|(i, x)| {
+            x.features
+                .iter()
+                .positions(|f| f.kind.toggle_head_movement().is_some())
+                .map(move |j| (i, j))
+        })
+        .choose(rng)?;
+    let feature = &mut entries[i].features[j];
+    feature.kind = feature.kind.toggle_head_movement()?;
+    Some(i)
+}
+
+///Copies an entry, giving the copy a new category. Returns the index of the original entry.
+fn duplicate_entry(entries: &mut Vec<Entry>, rng: &mut StdRng) -> Option<usize> {
+    let categories = names(entries, &[FeatureKind::Category]);
+    let i = (0..entries.len()).choose(rng)?;
+    let mut entry = entries[i].clone();
+    let category = entry.category().to_string();
+    let new_category = (0..)
+        .map(|i| format!("{category}_{i}"))
+        .find(|x| !categories.contains(x))?;
+    for feature in entry.features.iter_mut() {
+        if feature.kind == FeatureKind::Category {
+            feature.name = new_category.clone();
+        }
+    }
+    entries.push(entry);
+    Some(i)
+}
+
+#[pymethods]
+impl PyLexicon {
+    #[pyo3(signature = (seed, operations=None, max_attempts=100))]
+    ///Applies a random mutation to the lexicon, for use in evolutionary searches over grammars.
+    ///
+    ///Mutations which do not give a valid lexicon, or which make an entry the same as another, are
+    ///discarded and another is tried. The mutated lexicon keeps the token IDs of the original
+    ///lexicon, as well as the weight and metadata of each entry (a copy made by
+    ///``"duplicate_entry"`` gets those of its original).
+    ///
+    ///Parameters
+    ///----------
+    ///seed : int
+    ///    The seed of the random number generator.
+    ///operations : list of str or None, optional
+    ///    The operations which may be applied, out of ``"add_feature"`` (adding a selector,
+    ///    licensor or licensee using an existing name), ``"remove_feature"`` (removing any feature
+    ///    but the category), ``"duplicate_entry"`` (copying an entry with a new category),
+    ///    ``"swap_selectors"`` (swapping the order of two selectors of an entry) and
+    ///    ``"toggle_head_movement"`` (turning ``x=`` into ``=>x``, or ``=>x`` and ``x<=`` into
+    ///    ``x=``). If None, any operation may be applied.
+    ///    Default is None.
+    ///max_attempts : int, optional
+    ///    How many mutations to try before giving up.
+    ///    Default is 100.
+    ///
+    ///Returns
+    ///-------
+    ///tuple[Lexicon, str]
+    ///    The mutated lexicon and a description of the mutation, of the form
+    ///    ``"operation: old entry -> new entry"``.
+    ///
+    ///Raises
+    ///------
+    ///MGError
+    ///    If one of the operations is unknown, or if no valid mutation was found in
+    ///    ``max_attempts`` attempts.
+    fn mutate(
+        &self,
+        seed: u64,
+        operations: Option<Vec<String>>,
+        max_attempts: usize,
+    ) -> PyResult<(PyLexicon, String)> {
+        let operations = operations.unwrap_or_else(|| OPERATIONS.map(String::from).to_vec());
+        if let Some(unknown) = operations
+            .iter()
+            .find(|x| !OPERATIONS.contains(&x.as_str()))
+        {
+            return Err(MGError::new_err(format!(
+                "'{unknown}' is not a mutation operation"
+            )));
+        }
+
+        let given = self.given_weights();
+        let mut rng = StdRng::seed_from_u64(seed);
+        for _ in 0..max_attempts {
+            let Some(operation) = operations.choose(&mut rng) else {
+                break;
+            };
+            let mut entries = self.entries.clone();
+            let changed = match operation.as_str() {
+                "add_feature" => add_feature(&mut entries, &mut rng),
+                "remove_feature" => remove_feature(&mut entries, &mut rng),
+                "duplicate_entry" => duplicate_entry(&mut entries, &mut rng),
+                "swap_selectors" => swap_selectors(&mut entries, &mut rng),
+                _ => toggle_head_movement(&mut entries, &mut rng),
+            };
+            let Some(i) = changed else {
+                continue;
+            };
+            //An entry which became the same as another would silently merge their weights and
+            //metadata.
+            let after = if operation == "duplicate_entry" {
+                entries.len() - 1
+            } else {
+                i
+            };
+            if entries
+                .iter()
+                .enumerate()
+                .any(|(j, x)| j != after && *x == entries[after])
+            {
+                continue;
+            }
+
+            //Each mutated entry is paired with the entry it came from.
+            let sources = self.entries.iter().chain(std::iter::once(&self.entries[i]));
+            let pairs: Vec<(String, String)> = entries
+                .iter()
+                .zip(sources)
+                .map(|(new, old)| (new.to_string(), old.to_string()))
+                .collect();
+            let weights = given.as_ref().map(|given| {
+                pairs
+                    .iter()
+                    .map(|(new, old)| (new.clone(), given[old]))
+                    .collect()
+            });
+            let metadata = pairs
+                .iter()
+                .filter_map(|(new, old)| Some((new.clone(), self.metadata.get(old)?.clone())))
+                .collect();
+
+            let grammar = entries.iter().join("\n");
+            if let Ok(lexicon) = PyLexicon::new(
+                grammar,
+                Some(self.is_semantic()),
+                weights,
+                Some(self.word_id.ids().clone()),
+                Some(metadata),
+            ) {
+                let description = format!("{operation}: {} -> {}", self.entries[i], entries[after]);
+                return Ok((lexicon, description));
+            }
+        }
+        Err(MGError::new_err(format!(
+            "No valid mutation was found in {max_attempts} attempts"
+        )))
+    }
+}
@@ -0,0 +1,97 @@
+use rand::{prelude::*, rngs::StdRng};
+
+use crate::entries::{Entry, Feature, FeatureKind};
+
+///The shape of a randomly generated lexicon.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct RandomLexiconConfig {
+    pub n_categories: usize,
+    pub n_licensees: usize,
+    pub head_movement_prob: f64,
+    pub max_features: usize,
+    pub start_category: String,
+}
+
+impl RandomLexiconConfig {
+    fn category_names(&self) -> Vec<String> {
+        let mut names = vec![self.start_category.clone()];
+        let mut i = 0;
+        while names.len() < self.n_categories {
+            i += 1;
+            let name = i.to_string();
+            if name != self.start_category {
+                names.push(name);
+            }
+        }
+        names
+    }
+
+    fn complement(&self, name: &str, rng: &mut StdRng) -> Feature {
+        let kind = FeatureKind::RightSelector;
+        if rng.random_bool(self.head_movement_prob) {
+            Feature::new(kind.toggle_head_movement().unwrap_or(kind), name)
+        } else {
+            Feature::new(kind, name)
+        }
+    }
+
+    ///Samples the entries of a lexicon.
+    ///
+    ///Every category gets a base entry which is either a leaf or selects the last category, whose
+    ///base entry is always a leaf. Every category (in particular the start category) can then be
+    ///derived with at most one merge and no movement, however many categories there are. The
+    ///remaining lemmas are then given arbitrary entries.
+    pub(crate) fn sample(&self, lemmas: &[String], rng: &mut StdRng) -> Vec<Entry> {
+        let categories = self.category_names();
+        let licensees: Vec<String> = (0..self.n_licensees).map(|i| format!("l{i}")).collect();
+        let mut lemmas = lemmas.iter();
+        let mut entries = vec![];
+
+        for (i, category) in categories.iter().enumerate() {
+            let mut features = vec![];
+            if i + 1 < categories.len() && self.max_features > 1 && rng.random_bool(0.5) {
+                let leaf = &categories[categories.len() - 1];
+                features.push(self.complement(leaf, rng));
+            }
+            features.push(Feature::new(FeatureKind::Category, category.as_str()));
+            entries.push(Entry {
+                lemma: lemmas.next().cloned(),
+                features,
+                semantics: None,
+            });
+        }
+
+        for lemma in lemmas {
+            let n_features = rng.random_range(1..=self.max_features.max(1));
+            let category = &categories[rng.random_range(0..categories.len())];
+
+            let mut features = vec![];
+            let mut licensee_features = vec![];
+            for j in 1..n_features {
+                let roll = rng.random_range(0..4);
+                if !licensees.is_empty() && roll == 0 {
+                    let licensee = &licensees[rng.random_range(0..licensees.len())];
+                    licensee_features.push(Feature::new(FeatureKind::Licensee, licensee.as_str()));
+                } else if !licensees.is_empty() && roll == 1 && j > 1 {
+                    let licensor = &licensees[rng.random_range(0..licensees.len())];
+                    features.push(Feature::new(FeatureKind::Licensor, licensor.as_str()));
+                } else {
+                    let selected = &categories[rng.random_range(0..categories.len())];
+                    features.push(if features.is_empty() {
+                        self.complement(selected, rng)
+                    } else {
+                        Feature::new(FeatureKind::LeftSelector, selected.as_str())
+                    });
+                }
+            }
+            features.push(Feature::new(FeatureKind::Category, category.as_str()));
+            features.extend(licensee_features);
+            entries.push(Entry {
+                lemma: Some(lemma.clone()),
+                features,
+                semantics: None,
+            });
+        }
+        entries
+    }
+}
@@ -0,0 +1,141 @@
+use std::collections::{HashMap, HashSet};
+
+use itertools::Itertools;
+use pyo3::prelude::*;
+
+use crate::{
+    PyLexicon,
+    entries::{Entry, FeatureKind},
+    errors::MGError,
+};
+
+///Removes entries which can never be part of a complete derivation: those which select a category
+///no entry has, or which have a licensor or licensee that no other entry can check.
+fn prune(mut entries: Vec<&Entry>) -> Vec<&Entry> {
+    loop {
+        let names = |kind: FeatureKind| -> HashSet<&str> {
+            entries
+                .iter()
+                .flat_map(|x| x.features.iter())
+                .filter(|x| x.kind == kind)
+                .map(|x| x.name.as_str())
+                .collect()
+        };
+        let categories = names(FeatureKind::Category);
+        let licensors = names(FeatureKind::Licensor);
+        let licensees = names(FeatureKind::Licensee);
+
+        let n = entries.len();
+        entries.retain(|entry| {
+            entry.features.iter().all(|x| match x.kind {
+                FeatureKind::Category => true,
+                FeatureKind::Licensor => licensees.contains(x.name.as_str()),
+                FeatureKind::Licensee => licensors.contains(x.name.as_str()),
+                _ => categories.contains(x.name.as_str()),
+            })
+        });
+        if entries.len() == n {
+            return entries;
+        }
+    }
+}
+
+#[pymethods]
+impl PyLexicon {
+    #[pyo3(signature = (words=None, entries=None))]
+    ///Makes a smaller lexicon out of some of the words or entries of this one, e.g. for
+    ///curriculum learning.
+    ///
+    ///Entries which can no longer be used (because they select a category or need a licensor or
+    ///licensee which no remaining entry has) are removed as well. The new lexicon keeps the token
+    ///IDs of this one, so that they stay aligned, and the weights and metadata of the entries it
+    ///keeps.
+    ///
+    ///Parameters
+    ///----------
+    ///words : list of str or None, optional
+    ///    The words to keep. Entries without a pronunciation are kept unless they are excluded by
+    ///    ``entries``. If None, every word is kept.
+    ///    Default is None.
+    ///entries : list of str or None, optional
+    ///    The lexical entries to keep. Entries without semantics match any entry with the same
+    ///    syntax. If None, every entry is kept.
+    ///    Default is None.
+    ///
+    ///Returns
+    ///-------
+    ///Lexicon
+    ///    The entries of this lexicon which are chosen by both ``words`` and ``entries`` and can
+    ///    still be used.
+    ///
+    ///Raises
+    ///------
+    ///MGError
+    ///    If one of ``entries`` is not a valid lexical entry, or if no entries are left.
+    fn restrict(
+        &self,
+        words: Option<Vec<String>>,
+        entries: Option<Vec<String>>,
+    ) -> PyResult<PyLexicon> {
+        let words: Option<HashSet<String>> = words.map(|x| x.into_iter().collect());
+        let keys = entries
+            .map(|x| {
+                x.iter()
+                    .map(|key| {
+                        Entry::parse(key).map_err(|e| {
+                            MGError::new_err(format!(
+                                "'{key}' is not a valid lexical entry: {}",
+                                e.message
+                            ))
+                        })
+                    })
+                    .collect::<PyResult<Vec<_>>>()
+            })
+            .transpose()?;
+
+        let chosen = self
+            .entries
+            .iter()
+            .filter(|entry| match (&words, &entry.lemma) {
+                (Some(words), Some(lemma)) => words.contains(lemma),
+                _ => true,
+            })
+            .filter(|entry| {
+                keys.as_ref().is_none_or(|keys| {
+                    keys.iter().any(|key| {
+                        if key.semantics.is_some() {
+                            key == *entry
+                        } else {
+                            key.syntax() == entry.syntax()
+                        }
+                    })
+                })
+            })
+            .collect();
+        let kept = prune(chosen);
+        if kept.is_empty() {
+            return Err(MGError::new_err("No entries are left in the lexicon"));
+        }
+
+        let kept_entries: HashSet<String> = kept.iter().map(|x| x.to_string()).collect();
+        let weights: Option<HashMap<String, f64>> = self.weights().map(|weights| {
+            weights
+                .into_iter()
+                .filter(|(entry, _)| kept_entries.contains(entry))
+                .collect()
+        });
+        let metadata = self
+            .metadata
+            .iter()
+            .filter(|(entry, _)| kept_entries.contains(*entry))
+            .map(|(entry, values)| (entry.clone(), values.clone()))
+            .collect();
+        PyLexicon::new(
+            kept.iter().join("\n"),
+            Some(self.is_semantic()),
+            weights,
+            Some(self.word_id.ids().clone()),
+            Some(metadata),
+        )
+    }
+}
@@ -7,13 +7,15 @@ use std::{
 };
 
 use itertools::Itertools;
-use pyo3::{exceptions::PyValueError, prelude::*};
+use pyo3::prelude::*;
 use simple_semantics::{
     Entity, EventType, LanguageResult, PossibleEvent, Scenario, ScenarioIterator, ThetaRoles,
     lambda::{FreeVar, RootedLambdaPool},
     language::{ExecutionConfig, Expr},
 };
 
+use crate::errors::{LOTSyntaxError, LOTTypeError, execution_error};
+
 pub mod lot_types;
 use lot_types::{PyActor, PyEvent, convert_to_py_actor, convert_to_py_event};
 pub mod scenario;
@@ -85,7 +87,8 @@ impl PyMeaning {
     fn new(expr: String) -> PyResult<Self> {
         let string = Arc::new(expr);
         let s: &'static str = unsafe { std::mem::transmute(string.as_str()) };
-        let expr = RootedLambdaPool::parse(s).map_err(|e| PyValueError::new_err(e.to_string()))?;
+        let expr =
+            RootedLambdaPool::parse(s).map_err(|e| LOTSyntaxError::new_err(e.to_string()))?;
 
         Ok(Self {
             expr,
@@ -135,9 +138,10 @@ impl PyMeaning {
     ///
     ///Raises
     ///------
-    ///ValueError
-    ///    If the free variable's expression is of the wrong type if the meaning is an
-    ///    unparseable string.
+    ///LOTSyntaxError
+    ///    If the meaning is an unparseable string.
+    ///LOTTypeError
+    ///    If the free variable's expression is of the wrong type.
     #[pyo3(signature = (free_var, value, reduce=true))]
     fn bind_free_variable(
         &self,
@@ -160,11 +164,11 @@ impl PyMeaning {
         phi.strings.extend(strings);
         phi.expr
             .bind_free_variable(fvar, psi)
-            .map_err(|e| PyValueError::new_err(e.to_string()))?;
+            .map_err(|e| LOTTypeError::new_err(e.to_string()))?;
         if reduce {
             phi.expr
                 .reduce()
-                .map_err(|e| PyValueError::new_err(e.to_string()))?;
+                .map_err(|e| LOTTypeError::new_err(e.to_string()))?;
             phi.expr.cleanup();
         }
 
@@ -199,9 +203,10 @@ impl PyMeaning {
     ///
     ///Raises
     ///------
-    ///ValueError
-    ///    If the expression is of the wrong type or if the meaning is an
-    ///    unparseable string.
+    ///LOTSyntaxError
+    ///    If the meaning is an unparseable string.
+    ///LOTTypeError
+    ///    If the expression is of the wrong type.
     #[pyo3(signature = (psi, reduce=true))]
     fn apply(&self, psi: MeaningOrString, reduce: bool) -> PyResult<Option<PyMeaning>> {
         let PyMeaning {
@@ -216,7 +221,7 @@ impl PyMeaning {
         if let Some(mut phi) = phi.apply(psi) {
             if reduce {
                 phi.reduce()
-                    .map_err(|e| PyValueError::new_err(e.to_string()))?;
+                    .map_err(|e| LOTTypeError::new_err(e.to_string()))?;
                 phi.cleanup();
             }
             //strings may grow monotonically but its unlikely to ever actually be an issue!
@@ -235,13 +240,13 @@ impl PyMeaning {
     ///
     ///Raises
     ///------
-    ///ValueError
+    ///LOTTypeError
     ///    If there is an error in how the meaning is constructed leading the reduction to fail.
     fn reduce(&self) -> PyResult<Self> {
         let mut phi = self.clone();
         phi.expr
             .reduce()
-            .map_err(|e| PyValueError::new_err(e.to_string()))?;
+            .map_err(|e| LOTTypeError::new_err(e.to_string()))?;
         phi.expr.cleanup();
         Ok(phi)
     }
@@ -307,20 +312,20 @@ impl PyScenario {
     fn execute<'a>(
         &'a self,
         mut expr: RootedLambdaPool<'a, Expr<'a>>,
-        config: Option<ExecutionConfig>,
+        max_steps: Option<usize>,
+        timeout: Option<Duration>,
     ) -> PyResult<OwnedLanguageResult> {
         let scenario = self.as_scenario();
         expr.reduce()
-            .map_err(|e| PyValueError::new_err(e.to_string()))?;
+            .map_err(|e| LOTTypeError::new_err(e.to_string()))?;
         expr.cleanup();
 
         let pool = expr
             .into_pool()
-            .map_err(|e| PyValueError::new_err(e.to_string()))?;
+            .map_err(|e| LOTTypeError::new_err(e.to_string()))?;
 
-        let language_result = pool
-            .run(&scenario, config)
-            .map_err(|e| PyValueError::new_err(e.to_string()))?;
+        let config = ExecutionConfig::new(max_steps, timeout).allow_empty_quantification();
+        let language_result = pool.run(&scenario, Some(config)).map_err(execution_error)?;
         OwnedLanguageResult::new(language_result, &scenario)
     }
 }
@@ -340,12 +345,12 @@ impl PyScenario {
     ///    The scenario described by the string.
     ///Raises
     ///------
-    ///ValueError
+    ///LOTSyntaxError
     ///    If the expression is not a valid description of a scenario
     #[staticmethod]
     fn from_str(s: String) -> PyResult<Self> {
         let scenario =
-            Scenario::parse(s.as_str()).map_err(|e| PyValueError::new_err(e.to_string()))?;
+            Scenario::parse(s.as_str()).map_err(|e| LOTSyntaxError::new_err(e.to_string()))?;
         Ok(scenario.into())
     }
 
@@ -353,7 +358,7 @@ impl PyScenario {
         format!("Scenario({self})")
     }
 
-    ///Executes an language of thought expression in this scenario. Will potentially throw a PresuppositionException if
+    ///Executes an language of thought expression in this scenario. Will potentially throw a PresuppositionError if
     ///something is referenced that isn't in the scenario. It will also reduce any lambda
     ///expressions if possible, and then will only execute the expression if it is fully reducible.
     ///
@@ -373,10 +378,14 @@ impl PyScenario {
     ///    the value of the expression
     ///Raises
     ///------
-    ///ValueError
+    ///LOTSyntaxError
     ///    If the expression is a string which is incorrectly formatted.
+    ///LOTTypeError
     ///    If the expression's lambda terms cannot be fully reduced.
+    ///PresuppositionError
     ///    If there is a presupposition error.
+    ///ExecutionLimitError
+    ///    If the execution runs out of steps or time.
     ///
     #[pyo3(signature = (expression, max_steps=64, timeout=None))]
     fn evaluate(
@@ -387,7 +396,8 @@ impl PyScenario {
     ) -> PyResult<OwnedLanguageResult> {
         self.execute(
             expression.into_meaning()?.expr().clone(),
-            Some(ExecutionConfig::new(max_steps, timeout).allow_empty_quantification()),
+            max_steps,
+            timeout,
         )
     }
 
@@ -1,6 +1,8 @@
 use pyo3::prelude::*;
 use simple_semantics::Event;
 
+use crate::errors::MGError;
+
 use super::*;
 
 pub(super) fn convert_to_py_actor(name: &str, scenario: &Scenario<'_>) -> PyActor {
@@ -25,7 +27,7 @@ pub(super) fn convert_to_py_event(e_i: Event, scenario: &Scenario<'_>) -> Result
         .thematic_relations()
         .get(e_i as usize)
         .ok_or_else(|| {
-            PyValueError::new_err(format!(
+            MGError::new_err(format!(
                 "Result is event {e_i}, but no such event exists in the scenario!"
             ))
         })?;
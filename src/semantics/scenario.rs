@@ -9,14 +9,14 @@ use super::*;
 ///events: list[Event]
 ///    The events happening in the scenario
 ///events: list[str]
-///    The questions in a scenario. (Will raise a `ValueError` if set with a `str` which is not a
+///    The questions in a scenario. (Will raise a `LOTSyntaxError` if set with a `str` which is not a
 ///    valid Language of Thought expression)
 ///questions: list[str | Meaning]
 ///    Any questions to be asked in this scenario. (Must be LOT expressions)
 ///
 ///Raises
 ///------
-///ValueError
+///LOTSyntaxError
 ///    If the questions are strings which are not proper LOT expressions.
 #[pyclass(
     name = "Scenario",
@@ -1,6 +1,11 @@
-use std::{fmt::Display, sync::Arc};
+use std::{
+    fmt::Display,
+    hash::{Hash, Hasher},
+    sync::Arc,
+};
 
 use crate::{
+    errors::GrammarError,
     graphing::{PyMgEdge, PyMgNode},
     semantics::PyMeaning,
 };
@@ -8,9 +13,16 @@ use crate::{
 use super::PyLexicon;
 use logprob::LogProb;
 use minimalist_grammar_parser::{PhonContent, RulePool, lexicon::LexicalEntry};
-use pyo3::{exceptions::PyValueError, prelude::*};
+use pyo3::prelude::*;
 
-#[pyclass(name = "SyntacticStructure", str, eq, frozen)]
+#[pyclass(
+    name = "SyntacticStructure",
+    module = "python_mg",
+    str,
+    eq,
+    hash,
+    frozen
+)]
 #[derive(Debug)]
 ///The representation of a syntactic structure generated by a grammar, or alternatively the result
 ///of parsing a string.
@@ -28,6 +40,15 @@ impl PartialEq for PySyntacticStructure {
     }
 }
 
+///Only hashes the probability and string, since equal structures must have the same ones.
+impl Hash for PySyntacticStructure {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        //Adding 0.0 turns -0.0 into 0.0, since they are equal.
+        (self.prob.into_inner() + 0.0).to_bits().hash(state);
+        self.to_string().hash(state);
+    }
+}
+
 impl Display for PySyntacticStructure {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         let len = self.string.len();
@@ -108,6 +129,14 @@ impl PySyntacticStructure {
     pub fn string(&self) -> &Vec<PhonContent<String>> {
         &self.string
     }
+
+    pub fn log_probability(&self) -> LogProb<f64> {
+        self.prob
+    }
+
+    pub fn rules(&self) -> &RulePool {
+        &self.rules
+    }
 }
 
 #[pymethods]
@@ -157,11 +186,11 @@ impl PySyntacticStructure {
     ///
     ///Raises
     ///------
-    ///ValueException
+    ///GrammarSyntaxError
     ///    If the lexical entry is not parseable as a lexical entry.
     fn contains_lexical_entry(&self, s: &str) -> PyResult<bool> {
         let lex = self.lex.get();
-        let entry = LexicalEntry::parse(s).map_err(|e| PyValueError::new_err(e.to_string()))?;
+        let entry = LexicalEntry::parse(s).map_err(|e| GrammarError::locate(s, false, e))?;
         Ok(lex
             .lexeme_to_id
             .get(&entry)
@@ -0,0 +1,348 @@
+use std::collections::HashMap;
+
+use pyo3::prelude::*;
+
+use crate::{
+    PyLexicon,
+    errors::{GrammarError, MGError},
+};
+
+///The largest number of parameters whose settings are all expanded by
+///[`PyGrammarTemplate::expand`], i.e. at most 65536 lexicons.
+const MAX_EXPANDED_PARAMETERS: usize = 16;
+
+///A piece of a template, either text which is always present or a choice made by a parameter.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Piece {
+    Text(String),
+    Choice {
+        parameter: String,
+        on: String,
+        off: String,
+    },
+}
+
+///Reads the `[parameter: on | off]` blocks of a single line of a template.
+fn parse_line(line: &str, line_number: usize, pieces: &mut Vec<Piece>) -> PyResult<()> {
+    let column = |offset: usize| line[..offset].chars().count() + 1;
+    let error = |offset: usize, message: &str| -> PyErr {
+        GrammarError::at(line_number, column(offset), line, message).into()
+    };
+
+    let mut start = 0;
+    while start < line.len() {
+        let rest = &line[start..];
+        let Some(open) = rest.find(['[', ']']) else {
+            pieces.push(Piece::Text(rest.to_string()));
+            break;
+        };
+        if rest[open..].starts_with(']') {
+            return Err(error(start + open, "Unexpected ']' outside of a parameter"));
+        }
+        pieces.push(Piece::Text(rest[..open].to_string()));
+
+        let block = &rest[open + 1..];
+        let Some(close) = block.find(']') else {
+            return Err(error(start + open, "Unclosed '['"));
+        };
+        let block = &block[..close];
+        if let Some(nested) = block.find('[') {
+            return Err(error(
+                start + open + 1 + nested,
+                "Parameters cannot be nested",
+            ));
+        }
+        let Some((parameter, options)) = block.split_once(':') else {
+            return Err(error(
+                start + open,
+                "Expected '[parameter: present | absent]'",
+            ));
+        };
+        let parameter = parameter.trim();
+        if parameter.is_empty() || parameter.contains(char::is_whitespace) {
+            return Err(error(start + open + 1, "Invalid parameter name"));
+        }
+        let (on, off) = match split_alternatives(options).as_slice() {
+            [on] => (on.trim(), ""),
+            [on, off] => (on.trim(), off.trim()),
+            _ => {
+                return Err(error(
+                    start + open,
+                    "A parameter can have at most two alternatives",
+                ));
+            }
+        };
+        pieces.push(Piece::Choice {
+            parameter: parameter.to_string(),
+            on: on.to_string(),
+            off: off.to_string(),
+        });
+        start += open + close + 2;
+    }
+    Ok(())
+}
+
+///Splits the alternatives of a parameter on each `|` which is not inside parentheses, so that an
+///alternative can hold a semantic term such as `(p(x) | q(x))`.
+fn split_alternatives(options: &str) -> Vec<&str> {
+    let mut alternatives = vec![];
+    let mut depth = 0usize;
+    let mut start = 0;
+    for (i, c) in options.char_indices() {
+        match c {
+            '(' => depth += 1,
+            ')' => depth = depth.saturating_sub(1),
+            '|' if depth == 0 => {
+                alternatives.push(&options[start..i]);
+                start = i + 1;
+            }
+            _ => (),
+        }
+    }
+    alternatives.push(&options[start..]);
+    alternatives
+}
+
+///A lexicon with one setting of the parameters of a :class:`python_mg.GrammarTemplate`.
+#[pyclass(name = "GrammarVariant", module = "python_mg", frozen)]
+pub struct PyGrammarVariant {
+    parameters: HashMap<String, bool>,
+    vector: Vec<bool>,
+    lexicon: Py<PyLexicon>,
+}
+
+#[pymethods]
+impl PyGrammarVariant {
+    ///The setting of each parameter.
+    #[getter]
+    fn parameters(&self) -> HashMap<String, bool> {
+        self.parameters.clone()
+    }
+
+    ///The setting of each parameter, in the order of :attr:`python_mg.GrammarTemplate.parameters`.
+    #[getter]
+    fn vector(&self) -> Vec<bool> {
+        self.vector.clone()
+    }
+
+    ///The lexicon with these parameter settings.
+    #[getter]
+    fn lexicon(&self, py: Python<'_>) -> Py<PyLexicon> {
+        self.lexicon.clone_ref(py)
+    }
+
+    fn __repr__(&self) -> String {
+        format!("GrammarVariant({:?})", self.vector)
+    }
+}
+
+///A lexicon string with named binary parameters, which expands into a family of lexicons.
+///
+///Parameters are written ``[name: present | absent]``, which is replaced by ``present`` when the
+///parameter is ``True`` and ``absent`` otherwise. ``[name: present]`` is replaced by nothing when
+///the parameter is ``False``. Parameters can change the order of features, remove features, or
+///remove whole entries (lines which end up empty are ignored). A parameter may be used any number
+///of times. The alternatives are split on ``|``, so a ``|`` in a semantic term inside a parameter
+///has to be within parentheses, as in ``[p: lambda a x (pa_a(x) | pa_b(x)) | lambda a x 1]``.
+///
+///Parameters
+///----------
+///template : str
+///    The template of the lexicon.
+///semantic : bool or None, optional
+///    Whether the lexicons are semantic, as in :class:`python_mg.Lexicon`.
+///    Default is None.
+///
+///Raises
+///------
+///GrammarSyntaxError
+///    If a parameter block is malformed.
+///
+///Examples
+///--------
+///.. code-block:: python
+///
+///    template = GrammarTemplate(
+///        "John::d\n[wh: who::d -wh]\nsees::[head_initial: d= | =d] =d v\n::=v [wh: +wh] c"
+///    )
+///    for variant in template.expand():
+///        print(variant.parameters, variant.lexicon)
+#[pyclass(name = "GrammarTemplate", module = "python_mg", frozen)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PyGrammarTemplate {
+    pieces: Vec<Piece>,
+    parameters: Vec<String>,
+    semantic: Option<bool>,
+}
+
+impl PyGrammarTemplate {
+    ///The grammar with a setting of every parameter.
+    fn render(&self, settings: &HashMap<String, bool>) -> PyResult<String> {
+        if let Some(unknown) = settings.keys().find(|x| !self.parameters.contains(x)) {
+            return Err(MGError::new_err(format!(
+                "'{unknown}' is not a parameter of the template"
+            )));
+        }
+        let mut grammar = String::new();
+        for piece in &self.pieces {
+            match piece {
+                Piece::Text(text) => grammar.push_str(text),
+                Piece::Choice { parameter, on, off } => {
+                    let setting = settings.get(parameter).ok_or_else(|| {
+                        MGError::new_err(format!("Parameter '{parameter}' is not set"))
+                    })?;
+                    grammar.push_str(if *setting { on } else { off });
+                }
+            }
+        }
+        Ok(grammar)
+    }
+
+    fn variant(
+        &self,
+        py: Python<'_>,
+        settings: HashMap<String, bool>,
+    ) -> PyResult<PyGrammarVariant> {
+        let lexicon = PyLexicon::new(self.render(&settings)?, self.semantic, None, None, None)?;
+        Ok(PyGrammarVariant {
+            vector: self.parameters.iter().map(|x| settings[x]).collect(),
+            parameters: settings,
+            lexicon: Py::new(py, lexicon)?,
+        })
+    }
+}
+
+#[pymethods]
+impl PyGrammarTemplate {
+    #[new]
+    #[pyo3(signature = (template, semantic=None))]
+    fn new(template: &str, semantic: Option<bool>) -> PyResult<Self> {
+        let mut pieces = vec![];
+        for (i, line) in template.lines().enumerate() {
+            if i > 0 {
+                pieces.push(Piece::Text("\n".to_string()));
+            }
+            parse_line(line, i + 1, &mut pieces)?;
+        }
+
+        let mut parameters: Vec<String> = vec![];
+        for piece in &pieces {
+            if let Piece::Choice { parameter, .. } = piece
+                && !parameters.contains(parameter)
+            {
+                parameters.push(parameter.clone());
+            }
+        }
+
+        Ok(PyGrammarTemplate {
+            pieces,
+            parameters,
+            semantic,
+        })
+    }
+
+    ///The names of the parameters of the template, in the order they first appear.
+    #[getter]
+    fn parameters(&self) -> Vec<String> {
+        self.parameters.clone()
+    }
+
+    ///Gets the grammar string for a setting of the parameters.
+    ///
+    ///Parameters
+    ///----------
+    ///parameters : dict of str to bool
+    ///    The setting of every parameter of the template.
+    ///
+    ///Returns
+    ///-------
+    ///str
+    ///    The grammar, which can be passed to :class:`python_mg.Lexicon`.
+    ///
+    ///Raises
+    ///------
+    ///MGError
+    ///    If a parameter is missing or is not a parameter of the template.
+    fn grammar(&self, parameters: HashMap<String, bool>) -> PyResult<String> {
+        self.render(&parameters)
+    }
+
+    ///Builds the lexicon for a setting of the parameters.
+    ///
+    ///Parameters
+    ///----------
+    ///parameters : dict of str to bool
+    ///    The setting of every parameter of the template.
+    ///
+    ///Returns
+    ///-------
+    ///GrammarVariant
+    ///    The lexicon, tagged with its parameters.
+    ///
+    ///Raises
+    ///------
+    ///MGError
+    ///    If a parameter is missing or is not a parameter of the template.
+    ///GrammarSyntaxError
+    ///    If the resulting grammar is not a valid lexicon.
+    fn instantiate(
+        &self,
+        py: Python<'_>,
+        parameters: HashMap<String, bool>,
+    ) -> PyResult<PyGrammarVariant> {
+        self.variant(py, parameters)
+    }
+
+    #[pyo3(signature = (settings=None))]
+    ///Builds the lexicons for several settings of the parameters.
+    ///
+    ///Parameters
+    ///----------
+    ///settings : list of dict of str to bool or None, optional
+    ///    The settings to expand. If None, every one of the ``2 ** len(parameters)`` settings is
+    ///    expanded, in the order of counting in binary with the first parameter as the most
+    ///    significant bit.
+    ///    Default is None.
+    ///
+    ///Returns
+    ///-------
+    ///list of GrammarVariant
+    ///    The lexicon of each setting, tagged with its parameters.
+    ///
+    ///Raises
+    ///------
+    ///MGError
+    ///    If a parameter is missing or is not a parameter of the template, or if ``settings`` is
+    ///    None and the template has more than 16 parameters.
+    ///GrammarSyntaxError
+    ///    If one of the resulting grammars is not a valid lexicon.
+    fn expand(
+        &self,
+        py: Python<'_>,
+        settings: Option<Vec<HashMap<String, bool>>>,
+    ) -> PyResult<Vec<PyGrammarVariant>> {
+        let n = self.parameters.len();
+        if settings.is_none() && n > MAX_EXPANDED_PARAMETERS {
+            return Err(MGError::new_err(format!(
+                "Cannot expand every setting of {n} parameters (at most \
+                 {MAX_EXPANDED_PARAMETERS}); pass the settings to expand"
+            )));
+        }
+        let settings = settings.unwrap_or_else(|| {
+            (0..1usize << n)
+                .map(|k| {
+                    self.parameters
+                        .iter()
+                        .enumerate()
+                        .map(|(i, x)| (x.clone(), (k >> (n - 1 - i)) & 1 == 1))
+                        .collect()
+                })
+                .collect()
+        });
+        settings.into_iter().map(|x| self.variant(py, x)).collect()
+    }
+
+    fn __repr__(&self) -> String {
+        format!("GrammarTemplate(parameters={:?})", self.parameters)
+    }
+}
@@ -1,17 +1,20 @@
-use crate::get_config;
+use crate::errors::{MGError, TokenizationError};
 use crate::{PyLexicon, PySyntacticStructure};
 use minimalist_grammar_parser::PhonContent;
 use minimalist_grammar_parser::parsing::beam::Continuation;
 use numpy::PyUntypedArrayMethods;
-use numpy::ndarray::ArrayD;
+use numpy::ndarray::{ArrayD, IxDyn};
 use numpy::{PyArray1, PyArrayDyn, PyReadonlyArrayDyn};
-use pyo3::{exceptions::PyValueError, prelude::*};
+use pyo3::prelude::*;
 use std::collections::{HashMap, hash_map::Entry};
 
-const SOS: usize = 0;
-const EOS: usize = 1;
-const PAD: usize = 2;
-const AFFIX: usize = 3;
+pub(crate) const SOS: usize = 0;
+pub(crate) const EOS: usize = 1;
+pub(crate) const PAD: usize = 2;
+pub(crate) const AFFIX: usize = 3;
+
+///The largest n-gram model that will be allocated, in cells (128MB of floats).
+const MAX_NGRAM_CELLS: usize = 1 << 24;
 
 #[derive(Debug, Clone, Eq, PartialEq)]
 pub struct TokenMap(HashMap<String, usize>, HashMap<usize, String>);
@@ -36,6 +39,68 @@ impl TokenMap {
         self.0.len()
     }
 
+    pub fn ids(&self) -> &HashMap<String, usize> {
+        &self.0
+    }
+
+    pub fn get(&self, s: &str) -> Option<usize> {
+        self.0.get(s).copied()
+    }
+
+    pub fn word(&self, token: usize) -> Option<&str> {
+        self.1.get(&token).map(String::as_str)
+    }
+
+    ///Converts a string into tokens, starting with [SOS] and ending with [EOS].
+    pub fn tokenize<T: AsRef<str>>(&self, s: &[PhonContent<T>]) -> Vec<usize> {
+        let token = |w: &T| {
+            *self
+                .0
+                .get(w.as_ref())
+                .expect("Invalid string for this lexicon")
+        };
+
+        let mut output = vec![SOS];
+        for c in s {
+            match c {
+                PhonContent::Normal(w) => output.push(token(w)),
+                PhonContent::Affixed(items) => output.extend(
+                    items
+                        .iter()
+                        .flat_map(|w| [token(w), AFFIX].into_iter())
+                        .take(items.len() * 2 - 1), //Don't take the last affix
+                ),
+            }
+        }
+        output.push(EOS);
+        output
+    }
+
+    ///A token map with the given IDs, which must include every word of this one and keep the IDs
+    ///of the special tokens.
+    pub fn with_ids(&self, tokens: HashMap<String, usize>) -> PyResult<TokenMap> {
+        if let Some(word) = self.0.keys().find(|x| !tokens.contains_key(*x)) {
+            return Err(MGError::new_err(format!(
+                "The token IDs are missing '{word}'"
+            )));
+        }
+        for special in ["[SOS]", "[EOS]", "[PAD]", "[AFFIX]"] {
+            if tokens[special] != self.0[special] {
+                return Err(MGError::new_err(format!(
+                    "{special} must have the token ID {}",
+                    self.0[special]
+                )));
+            }
+        }
+        let inverse: HashMap<usize, String> = tokens.iter().map(|(s, n)| (*n, s.clone())).collect();
+        if inverse.len() != tokens.len() || (0..tokens.len()).any(|n| !inverse.contains_key(&n)) {
+            return Err(MGError::new_err(
+                "Token IDs must be unique and run from 0 to the number of tokens",
+            ));
+        }
+        Ok(TokenMap(tokens, inverse))
+    }
+
     pub fn add_word(&mut self, s: &str) {
         let n = self.0.len();
         match self.0.entry(s.to_string()) {
@@ -56,19 +121,19 @@ fn to_phon_content(s: &[usize], lex: &TokenMap) -> PyResult<Vec<PhonContent<Stri
 
     let w = *s
         .get(end)
-        .ok_or(PyErr::new::<PyValueError, _>("Empty string"))?;
+        .ok_or(PyErr::new::<TokenizationError, _>("Empty string"))?;
 
     if w != EOS {
-        return Err(PyErr::new::<PyValueError, _>("No end symbol"));
+        return Err(PyErr::new::<TokenizationError, _>("No end symbol"));
     }
 
     let mut i = 0;
     let w = *s
         .get(i)
-        .ok_or(PyErr::new::<PyValueError, _>("Empty string"))?;
+        .ok_or(PyErr::new::<TokenizationError, _>("Empty string"))?;
     i += 1;
     if w != SOS {
-        return Err(PyErr::new::<PyValueError, _>("No start symbol"));
+        return Err(PyErr::new::<TokenizationError, _>("No start symbol"));
     }
 
     let mut was_affixed = false;
@@ -78,12 +143,12 @@ fn to_phon_content(s: &[usize], lex: &TokenMap) -> PyResult<Vec<PhonContent<Stri
     while i < end {
         let c = *s.get(i).unwrap();
         if c == AFFIX {
-            return Err(PyErr::new::<PyValueError, _>("Too many affix symbols"));
+            return Err(PyErr::new::<TokenizationError, _>("Too many affix symbols"));
         }
         let w = lex
             .1
             .get(&c)
-            .ok_or(PyErr::new::<PyValueError, _>("Out of vocabulary"))?
+            .ok_or(PyErr::new::<TokenizationError, _>("Out of vocabulary"))?
             .clone();
 
         let next_is_affix = s.get(i + 1).is_some_and(|&x| x == AFFIX);
@@ -104,7 +169,7 @@ fn to_phon_content(s: &[usize], lex: &TokenMap) -> PyResult<Vec<PhonContent<Stri
     }
 
     if !affix_v.is_empty() {
-        return Err(PyErr::new::<PyValueError, _>("Trailing affix!"));
+        return Err(PyErr::new::<TokenizationError, _>("Trailing affix!"));
     }
     Ok(v)
 }
@@ -171,28 +236,30 @@ impl PyLexicon {
     ) -> PyResult<Bound<'py, PyArrayDyn<bool>>> {
         let original_shape: Vec<usize> = x.shape().to_vec();
         if original_shape.is_empty() {
-            return Err(PyValueError::new_err("Target shape is empty!"));
+            return Err(TokenizationError::new_err("Target shape is empty!"));
         }
         let d: usize = original_shape
             .iter()
             .take(original_shape.len() - 1)
             .product();
         if d == 0 {
-            return Err(PyValueError::new_err("Target shape has empty dimension!"));
+            return Err(TokenizationError::new_err(
+                "Target shape has empty dimension!",
+            ));
         }
 
         let z = x.as_array();
 
         let z = z
             .to_shape((d, *original_shape.last().unwrap()))
-            .map_err(|x| PyValueError::new_err(x.to_string()))?;
+            .map_err(|x| MGError::new_err(x.to_string()))?;
 
         let mut continuation_matrix = ArrayD::from_elem(
             vec![d, *original_shape.last().unwrap(), slf.word_id.len()],
             false,
         );
 
-        let config = get_config(min_log_prob, move_prob, max_steps, n_beams)?;
+        let search = slf.search(min_log_prob, move_prob, max_steps, n_beams)?;
 
         let tokens = &slf.word_id;
         for (i, row) in z.rows().into_iter().enumerate() {
@@ -240,16 +307,12 @@ impl PyLexicon {
                     last_was_affix = false;
                 }
 
-                let cont = slf
-                    .lexicon
-                    .lexicon()
-                    .valid_continuations(&category, &v, &config)
-                    .map_err(|x| PyValueError::new_err(x.to_string()))?;
+                let cont = slf.continuations_of(category, &v, &search)?;
 
                 for next in cont {
                     match next {
                         Continuation::Word(w) => {
-                            let c = *tokens.0.get(w).unwrap();
+                            let c = *tokens.0.get(&w).unwrap();
                             (*continuation_matrix.get_mut([i, j, c]).unwrap()) = true;
                         }
                         Continuation::AffixedWord(items) => {
@@ -257,7 +320,7 @@ impl PyLexicon {
 
                             let items = items
                                 .into_iter()
-                                .flat_map(|w| [*tokens.0.get(w).unwrap(), AFFIX].into_iter())
+                                .flat_map(|w| [*tokens.0.get(&w).unwrap(), AFFIX].into_iter())
                                 .take(n);
 
                             let mut last = None;
@@ -287,7 +350,7 @@ impl PyLexicon {
             py,
             continuation_matrix
                 .into_shape_with_order(target_shape)
-                .map_err(|e| PyValueError::new_err(e.to_string()))?,
+                .map_err(|e| MGError::new_err(e.to_string()))?,
         );
 
         Ok(v)
@@ -344,9 +407,98 @@ impl PyLexicon {
             .collect()
     }
 
+    #[allow(clippy::too_many_arguments)]
+    #[pyo3(signature = (category, n, min_log_prob=None, move_prob=0.5, max_steps=64, n_beams=None, max_strings=None))]
+    ///Computes the exact n-gram model of the strings of the grammar, using the distribution over
+    ///strings found by a bounded search of the grammar (as in
+    ///:meth:`python_mg.Lexicon.generate_unique_strings`).
+    ///
+    ///Strings are tokenized as in :meth:`python_mg.SyntacticStructure.tokens`, with ``n - 1``
+    ///[SOS] tokens at the start so that the first words have a full context.
+    ///
+    ///Parameters
+    ///----------
+    ///category : str
+    ///    The syntactic category to be generated.
+    ///n : int
+    ///    The order of the model, e.g. 2 for bigrams.
+    ///min_log_prob : float or None, optional
+    ///    Minimum log probability threshold to be generated.
+    ///    If none, there is no limit on log probability.
+    ///    Default is None.
+    ///move_prob : float, optional
+    ///    Probability of preferring a move over a merge when parsing.
+    ///    Default is 0.5
+    ///max_steps : int or None, optional
+    ///    Maximum number of derivation steps. If None, will not be limited.
+    ///    Default is 64.
+    ///n_beams : int or None, optional
+    ///    Number of beams to maintain while parsing. If None, will not be limited.
+    ///    Default is None.
+    ///max_strings : int or None, optional
+    ///    Number of strings to generate before stopping.
+    ///    Default is None.
+    ///
+    ///Returns
+    ///-------
+    ///ndarray of float, shape (C, ..., C)
+    ///    An array with ``n`` dimensions of size C, the vocabulary size, where
+    ///    ``x[w_1, ..., w_n]`` is the log probability of ``w_n`` following ``w_1, ..., w_{n-1}``.
+    ///    Contexts which never occur are ``-inf`` everywhere.
+    ///
+    ///Raises
+    ///------
+    ///MGError
+    ///    If ``n`` is 0 or if the model would have more than 2**24 cells.
+    fn ngram_model<'py>(
+        slf: PyRef<'py, Self>,
+        category: &str,
+        n: usize,
+        min_log_prob: Option<f64>,
+        move_prob: f64,
+        max_steps: Option<usize>,
+        n_beams: Option<usize>,
+        max_strings: Option<usize>,
+    ) -> PyResult<Bound<'py, PyArrayDyn<f64>>> {
+        if n == 0 {
+            return Err(MGError::new_err("n must be at least 1"));
+        }
+        let n_cells = u32::try_from(n)
+            .ok()
+            .and_then(|n| slf.word_id.len().checked_pow(n));
+        if n_cells.is_none_or(|x| x > MAX_NGRAM_CELLS) {
+            return Err(MGError::new_err(format!(
+                "The {n}-gram model would have more than {MAX_NGRAM_CELLS} cells"
+            )));
+        }
+        let search = slf.search(min_log_prob, move_prob, max_steps, n_beams)?;
+        let distribution = slf.string_distribution(category, &search, max_strings)?;
+
+        let mut counts: HashMap<Vec<usize>, f64> = HashMap::new();
+        for (string, log_prob) in &distribution {
+            let mut tokens = vec![SOS; n - 1];
+            tokens.extend(slf.word_id.tokenize(string).into_iter().skip(1));
+            for ngram in tokens.windows(n) {
+                *counts.entry(ngram.to_vec()).or_default() += log_prob.into_inner().exp();
+            }
+        }
+
+        let mut totals: HashMap<&[usize], f64> = HashMap::new();
+        for (ngram, count) in &counts {
+            *totals.entry(&ngram[..n - 1]).or_default() += count;
+        }
+
+        let mut model = ArrayD::from_elem(IxDyn(&vec![slf.word_id.len(); n]), f64::NEG_INFINITY);
+        for (ngram, count) in &counts {
+            model[ngram.as_slice()] = (count / totals[&ngram[..n - 1]]).ln();
+        }
+
+        Ok(PyArrayDyn::from_owned_array(slf.py(), model))
+    }
+
     #[allow(clippy::too_many_arguments)]
     #[pyo3(signature = (s, category, min_log_prob=-128.0, move_prob=0.5, max_steps=64, n_beams=256, max_parses=None))]
-    ///Converts a sequence of tokens into a list of SyntacticStructure. Will throw a ValueError if
+    ///Converts a sequence of tokens into a list of SyntacticStructure. Will throw a TokenizationError if
     ///the tokens are not formatted properly (but the list will be  empty if there is no parse).
     ///
     ///Parameters
@@ -411,30 +563,7 @@ impl PySyntacticStructure {
     ///ndarray of uint
     ///    the tokenized string.
     fn tokens<'py>(slf: PyRef<'py, Self>) -> Bound<'py, PyArray1<usize>> {
-        let tokens = slf.lex().get().tokens();
-
-        let mut output = vec![SOS];
-        for c in slf.string() {
-            match c {
-                PhonContent::Normal(w) => output.push(
-                    *tokens
-                        .get(w)
-                        .expect("Invalid syntactic structure for this lexicon"),
-                ),
-                PhonContent::Affixed(items) => output.extend(
-                    items
-                        .iter()
-                        .flat_map(|w| {
-                            let token = *tokens
-                                .get(w)
-                                .expect("Invalid syntactic structure for this lexicon");
-                            [token, AFFIX].into_iter()
-                        })
-                        .take(items.len() * 2 - 1), //Don't take the last affix
-                ),
-            }
-        }
-        output.push(EOS);
+        let output = slf.lex().get().word_id.tokenize(slf.string());
         let py = slf.py();
         PyArray1::from_vec(py, output)
     }
@@ -0,0 +1,182 @@
+use std::collections::{BTreeSet, HashMap};
+
+use itertools::Itertools;
+use pyo3::prelude::*;
+
+use crate::{
+    PyLexicon,
+    entries::{Entry, FeatureKind},
+};
+
+///The result of combining two lexicons with :meth:`python_mg.Lexicon.union`.
+#[pyclass(name = "LexiconUnion", module = "python_mg", frozen)]
+pub struct PyLexiconUnion {
+    lexicon: Py<PyLexicon>,
+    clashes: Vec<String>,
+    renamed: HashMap<String, String>,
+    homophones: Vec<(String, String)>,
+    semantics_dropped: bool,
+}
+
+#[pymethods]
+impl PyLexiconUnion {
+    ///The combined lexicon.
+    #[getter]
+    fn lexicon(&self, py: Python<'_>) -> Py<PyLexicon> {
+        self.lexicon.clone_ref(py)
+    }
+
+    ///The categories which both lexicons have entries of.
+    #[getter]
+    fn clashes(&self) -> Vec<String> {
+        self.clashes.clone()
+    }
+
+    ///The new name of each category of the other lexicon which was renamed.
+    #[getter]
+    fn renamed(&self) -> HashMap<String, String> {
+        self.renamed.clone()
+    }
+
+    ///Pairs of entries, one from each lexicon, which have the same pronunciation but different
+    ///features.
+    #[getter]
+    fn homophones(&self) -> Vec<(String, String)> {
+        self.homophones.clone()
+    }
+
+    ///Whether the semantic terms of one lexicon were dropped because the other lexicon is not
+    ///semantic.
+    #[getter]
+    fn semantics_dropped(&self) -> bool {
+        self.semantics_dropped
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "LexiconUnion(clashes={:?}, homophones={})",
+            self.clashes,
+            self.homophones.len()
+        )
+    }
+}
+
+///The categories of the entries.
+fn categories(entries: &[Entry]) -> BTreeSet<String> {
+    entries.iter().map(|x| x.category().to_string()).collect()
+}
+
+#[pymethods]
+impl PyLexicon {
+    #[pyo3(signature = (other, rename_conflicts=false, namespace=None))]
+    ///Combines the entries of this lexicon with those of another, e.g. to compose grammar
+    ///fragments written separately.
+    ///
+    ///Categories which only one lexicon has entries of link the two lexicons together (e.g. a
+    ///clause fragment selecting ``d`` and a fragment of determiner phrases of category ``d``).
+    ///Categories which both lexicons have entries of are clashes, which are either merged or (if
+    ///``rename_conflicts`` is set) renamed in the other lexicon.
+    ///
+    ///If only one of the lexicons is semantic, the union is not semantic and the semantic terms
+    ///are dropped. The union is not weighted. It keeps the token IDs of this lexicon, with the new
+    ///words of the other lexicon after them.
+    ///
+    ///Parameters
+    ///----------
+    ///other : Lexicon
+    ///    The lexicon to combine with this one.
+    ///rename_conflicts : bool, optional
+    ///    Whether to rename clashing categories of the other lexicon (wherever the other lexicon
+    ///    uses them), rather than merging them with the categories of this lexicon.
+    ///    Default is False.
+    ///namespace : str or None, optional
+    ///    The prefix of renamed categories, giving ``namespace_category`` (with a numbered suffix
+    ///    if that name is already used). If None, renamed categories are given a numbered suffix
+    ///    instead, as in ``d_0``.
+    ///    Default is None.
+    ///
+    ///Returns
+    ///-------
+    ///LexiconUnion
+    ///    The combined lexicon, along with the clashing categories, how they were renamed and any
+    ///    homophonous entries.
+    fn union(
+        &self,
+        py: Python<'_>,
+        other: &PyLexicon,
+        rename_conflicts: bool,
+        namespace: Option<&str>,
+    ) -> PyResult<PyLexiconUnion> {
+        let ours = categories(&self.entries);
+        let theirs = categories(&other.entries);
+        let clashes: Vec<String> = ours.intersection(&theirs).cloned().collect();
+
+        let mut renamed = HashMap::new();
+        let mut other_entries = other.entries.clone();
+        if rename_conflicts {
+            let mut used: BTreeSet<String> = self
+                .entries
+                .iter()
+                .chain(other.entries.iter())
+                .flat_map(|x| x.features.iter())
+                .filter(|x| x.kind == FeatureKind::Category || x.kind.is_selector())
+                .map(|x| x.name.clone())
+                .collect();
+            for category in &clashes {
+                let base = namespace.map(|namespace| format!("{namespace}_{category}"));
+                let prefix = base.as_deref().unwrap_or(category);
+                let name = base
+                    .iter()
+                    .cloned()
+                    .chain((0..).map(|i| format!("{prefix}_{i}")))
+                    .find(|x| !used.contains(x))
+                    .expect("There are always unused names");
+                used.insert(name.clone());
+                for entry in other_entries.iter_mut() {
+                    entry.rename_category(category, &name);
+                }
+                renamed.insert(category.clone(), name);
+            }
+        }
+
+        let homophones = self
+            .entries
+            .iter()
+            .cartesian_product(other_entries.iter())
+            .filter(|(a, b)| a.lemma.is_some() && a.lemma == b.lemma && a.syntax() != b.syntax())
+            .map(|(a, b)| (a.to_string(), b.to_string()))
+            .collect();
+
+        let semantic = self.is_semantic() && other.is_semantic();
+        let semantics_dropped = self.is_semantic() != other.is_semantic();
+        let entries: Vec<Entry> = self
+            .entries
+            .iter()
+            .cloned()
+            .chain(other_entries)
+            .map(|mut entry| {
+                if !semantic {
+                    entry.semantics = None;
+                }
+                entry
+            })
+            .unique()
+            .collect();
+
+        let mut lexicon =
+            PyLexicon::new(entries.iter().join("\n"), Some(semantic), None, None, None)?;
+        let mut tokens = self.word_id.clone();
+        for (word, _) in lexicon.word_id.ids().iter().sorted_by_key(|(_, id)| **id) {
+            tokens.add_word(word);
+        }
+        lexicon.word_id = tokens;
+
+        Ok(PyLexiconUnion {
+            lexicon: Py::new(py, lexicon)?,
+            clashes,
+            renamed,
+            homophones,
+            semantics_dropped,
+        })
+    }
+}
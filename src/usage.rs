@@ -0,0 +1,173 @@
+use std::collections::HashMap;
+
+use minimalist_grammar_parser::{PhonContent, lexicon::LexemeId};
+use pyo3::prelude::*;
+
+use crate::{
+    PyLexicon,
+    entries::{Entry, FeatureKind},
+    errors::MGError,
+    mdl::{bits, log_sum_exp},
+};
+
+///How often a lexicon uses each of its entries and operations per sentence, on average.
+#[pyclass(name = "ExpectedUsage", module = "python_mg", frozen, get_all)]
+#[derive(Debug, Clone, PartialEq)]
+pub struct PyExpectedUsage {
+    ///The expected number of times each lexical entry is used in a derivation, keyed by the entry
+    ///(without its semantics). Entries which only differ in their semantics are counted together.
+    entries: HashMap<String, f64>,
+    ///The expected number of ``"merge"``, ``"move"`` and ``"head_move"`` operations in a
+    ///derivation. Selecting a complement with head movement counts as both a merge and a head move.
+    operations: HashMap<String, f64>,
+    ///The entropy (in bits) of the distribution over strings.
+    entropy: f64,
+    ///The expected number of words in a string.
+    expected_length: f64,
+    ///The total probability of the derivations that were found. The other values are computed
+    ///from these derivations, renormalised to sum to one.
+    coverage: f64,
+}
+
+#[pymethods]
+impl PyExpectedUsage {
+    fn __repr__(&self) -> String {
+        format!(
+            "ExpectedUsage(entropy={}, expected_length={}, coverage={})",
+            self.entropy, self.expected_length, self.coverage
+        )
+    }
+}
+
+///How many of each operation using an entry requires.
+fn operations(entry: &Entry) -> [(&'static str, usize); 3] {
+    let count = |f: fn(&FeatureKind) -> bool| entry.features.iter().filter(|x| f(&x.kind)).count();
+    [
+        ("merge", count(FeatureKind::is_selector)),
+        ("move", count(|x| *x == FeatureKind::Licensor)),
+        (
+            "head_move",
+            count(|x| matches!(x, FeatureKind::LeftAffix | FeatureKind::RightAffix)),
+        ),
+    ]
+}
+
+#[pymethods]
+impl PyLexicon {
+    #[pyo3(signature = (category, min_log_prob=None, move_prob=0.5, max_steps=64, n_beams=None, max_derivations=None))]
+    ///Computes how often each lexical entry and each operation is used per sentence, along with
+    ///the entropy and expected length of the strings of the grammar.
+    ///
+    ///The expectations are taken over every derivation found by a bounded search of the grammar
+    ///(see :meth:`python_mg.Lexicon.generate_grammar`), using the weights of the lexicon if it has
+    ///any.
+    ///
+    ///Parameters
+    ///----------
+    ///category : str
+    ///    The syntactic category to be generated.
+    ///min_log_prob : float or None, optional
+    ///    Minimum log probability threshold to be generated.
+    ///    If none, there is no limit on log probability.
+    ///    Default is None.
+    ///move_prob : float, optional
+    ///    Probability of preferring a move over a merge when parsing.
+    ///    Default is 0.5
+    ///max_steps : int or None, optional
+    ///    Maximum number of derivation steps. If None, will not be limited.
+    ///    Default is 64.
+    ///n_beams : int or None, optional
+    ///    Number of beams to maintain while parsing. If None, will not be limited.
+    ///    Default is None.
+    ///max_derivations : int or None, optional
+    ///    Number of derivations to generate before stopping.
+    ///    Default is None.
+    ///
+    ///Returns
+    ///-------
+    ///ExpectedUsage
+    ///    The expected usage of each entry and operation, the entropy, the expected length and
+    ///    the probability mass covered by the search.
+    fn expected_usage(
+        &self,
+        category: &str,
+        min_log_prob: Option<f64>,
+        move_prob: f64,
+        max_steps: Option<usize>,
+        n_beams: Option<usize>,
+        max_derivations: Option<usize>,
+    ) -> PyResult<PyExpectedUsage> {
+        let search = self.search(min_log_prob, move_prob, max_steps, n_beams)?;
+        let mut lexemes: HashMap<LexemeId, &Entry> = HashMap::new();
+        for entry in &self.entries {
+            lexemes.insert(self.lexeme_id(entry)?, entry);
+        }
+
+        let mut derivations = vec![];
+        let mut strings: HashMap<Vec<PhonContent<&str>>, Vec<f64>> = HashMap::new();
+        let generator = self
+            .lexicon
+            .lexicon()
+            .generate(category, &search.config)
+            .map_err(|e| MGError::new_err(e.to_string()))?;
+        for (prob, string, rules) in generator {
+            if max_derivations.is_some_and(|max| derivations.len() >= max) {
+                break;
+            }
+            let Some(log_prob) = self.reweight_within(&search, prob, &rules)? else {
+                continue;
+            };
+            let log_prob = log_prob.into_inner();
+            strings.entry(string).or_default().push(log_prob);
+            derivations.push((log_prob, rules));
+        }
+
+        let total = log_sum_exp(derivations.iter().map(|(p, _)| *p));
+        if derivations.is_empty() || total == f64::NEG_INFINITY {
+            return Err(MGError::new_err(format!(
+                "No derivations of {category} were found"
+            )));
+        }
+
+        let mut entries: HashMap<String, f64> = self
+            .entries
+            .iter()
+            .map(|entry| (entry.syntax(), 0.0))
+            .collect();
+        let mut operation_counts: HashMap<String, f64> = ["merge", "move", "head_move"]
+            .into_iter()
+            .map(|x| (x.to_string(), 0.0))
+            .collect();
+        for (log_prob, rules) in &derivations {
+            let p = (log_prob - total).exp();
+            for id in rules.used_lemmas() {
+                let Some(entry) = lexemes.get(&id) else {
+                    continue;
+                };
+                *entries.get_mut(&entry.syntax()).unwrap() += p;
+                for (operation, n) in operations(entry) {
+                    *operation_counts.get_mut(operation).unwrap() += p * n as f64;
+                }
+            }
+        }
+
+        let mut entropy = 0.0;
+        let mut expected_length = 0.0;
+        for (string, log_probs) in &strings {
+            let log_prob = log_sum_exp(log_probs.iter().copied()) - total;
+            let p = log_prob.exp();
+            if p > 0.0 {
+                entropy += p * bits(log_prob);
+            }
+            expected_length += p * string.len() as f64;
+        }
+
+        Ok(PyExpectedUsage {
+            entries,
+            operations: operation_counts,
+            entropy,
+            expected_length,
+            coverage: total.exp(),
+        })
+    }
+}
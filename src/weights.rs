@@ -0,0 +1,567 @@
+use std::collections::{HashMap, HashSet};
+
+use itertools::Itertools;
+use logprob::LogProb;
+use minimalist_grammar_parser::{
+    ParsingConfig, PhonContent, RulePool, lexicon::LexemeId, parsing::beam::Continuation,
+};
+use pyo3::prelude::*;
+
+use crate::{
+    PyLexicon,
+    entries::{Entry, Feature},
+    errors::{GrammarError, LexiconError, MGError},
+    get_config, map_string,
+    mdl::log_sum_exp,
+};
+
+///Splits the `@ <weight>` annotations off the lines of a grammar and combines them with the
+///weights given as a dictionary (which take precedence).
+///
+///Entries with a weight of zero are replaced by empty lines (so that errors still point to the
+///right line of the original grammar). If no entry has a weight, the weights are `None`, otherwise
+///there is one weight for each remaining entry and unweighted entries have a weight of 1.
+pub(crate) fn split_weights(
+    grammar: &str,
+    weights: Option<HashMap<String, f64>>,
+) -> Result<(String, Option<Vec<f64>>), LexiconError> {
+    let keys = weights
+        .unwrap_or_default()
+        .into_iter()
+        .map(|(key, weight)| match Entry::parse(&key) {
+            Ok(entry) => Ok((entry, key, weight)),
+            Err(e) => Err(MGError::new_err(format!(
+                "'{key}' is not a valid lexical entry: {}",
+                e.message
+            ))),
+        })
+        .collect::<PyResult<Vec<_>>>()?;
+    let mut used_keys = vec![false; keys.len()];
+
+    let mut lines = vec![];
+    let mut entry_weights = vec![];
+    for (i, line) in grammar.lines().enumerate() {
+        let (text, mut weight) = match line.rsplit_once('@') {
+            Some((text, weight)) => {
+                let column = text.chars().count() + 2;
+                let weight = weight.trim().parse::<f64>().map_err(|e| {
+                    GrammarError::at(i + 1, column, line, format!("Invalid weight: {e}"))
+                })?;
+                (text, Some(weight))
+            }
+            None => (line, None),
+        };
+
+        if let Ok(entry) = Entry::parse(text) {
+            for (j, (key, _, key_weight)) in keys.iter().enumerate() {
+                let matches = if key.semantics.is_some() {
+                    *key == entry
+                } else {
+                    key.syntax() == entry.syntax()
+                };
+                if matches {
+                    weight = Some(*key_weight);
+                    used_keys[j] = true;
+                }
+            }
+        }
+
+        match weight {
+            Some(weight) if !(weight.is_finite() && weight >= 0.0) => {
+                return Err(GrammarError::at(
+                    i + 1,
+                    text.chars().count() + 1,
+                    line,
+                    format!("Weights must be finite and non-negative, not {weight}"),
+                )
+                .into());
+            }
+            Some(weight) if weight == 0.0 => lines.push(""),
+            _ => {
+                lines.push(text.trim_end());
+                if !text.trim().is_empty() {
+                    entry_weights.push(weight);
+                }
+            }
+        }
+    }
+
+    if let Some(j) = used_keys.iter().position(|used| !used) {
+        return Err(
+            MGError::new_err(format!("'{}' is not an entry of the grammar", keys[j].1)).into(),
+        );
+    }
+
+    let entry_weights = entry_weights
+        .iter()
+        .any(|x| x.is_some())
+        .then(|| entry_weights.iter().map(|x| x.unwrap_or(1.0)).collect());
+    Ok((lines.join("\n"), entry_weights))
+}
+
+///A step from one node of the lexicon to the next: either another feature or a lemma.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum Child<'a> {
+    Feature(&'a Feature),
+    Lemma(Option<&'a str>),
+}
+
+///The probability the parser gives each entry once it has decided which feature it is looking
+///for. The lexicon is a tree of features, read from the last feature of each entry to the first,
+///and the parser picks uniformly between the children of each node of the tree.
+fn uniform_probabilities(entries: &[Entry]) -> Vec<f64> {
+    let mut children: HashMap<&[Feature], HashSet<Child>> = HashMap::new();
+    let reversed: Vec<Vec<Feature>> = entries
+        .iter()
+        .map(|x| x.features.iter().rev().cloned().collect())
+        .collect();
+
+    for (entry, features) in entries.iter().zip(reversed.iter()) {
+        for i in 1..features.len() {
+            children
+                .entry(&features[..i])
+                .or_default()
+                .insert(Child::Feature(&features[i]));
+        }
+        children
+            .entry(features.as_slice())
+            .or_default()
+            .insert(Child::Lemma(entry.lemma.as_deref()));
+    }
+
+    reversed
+        .iter()
+        .map(|features| {
+            (1..=features.len())
+                .map(|i| 1.0 / children[&features[..i]].len() as f64)
+                .product()
+        })
+        .collect()
+}
+
+///How far above zero rounding may push the weighted log probability of a derivation.
+const ROUNDING: f64 = 1e-9;
+
+///The probabilities of the entries of a weighted lexicon.
+///
+///The weights of entries of the same category are normalised to sum to one. The probability of a
+///derivation is then the probability the parser gives it, with the probability of choosing each of
+///its lexical entries swapped for the weighted one.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Weights {
+    ///The weight each entry was given, in the same order as [`crate::PyLexicon`]'s entries.
+    given: Vec<f64>,
+    ///The probability of each entry, in the same order as [`crate::PyLexicon`]'s entries.
+    entries: Vec<LogProb<f64>>,
+    ///The weighted and the unweighted probability of each lexeme.
+    lexemes: HashMap<LexemeId, (LogProb<f64>, LogProb<f64>)>,
+}
+
+impl Weights {
+    ///Normalises the weights of `entries`, which are the lexemes `ids`.
+    pub(crate) fn new(entries: &[Entry], weights: &[f64], ids: &[LexemeId]) -> PyResult<Self> {
+        //The weights are summed in sorted order, so that the probabilities do not depend on the
+        //order of the entries.
+        let mut groups: HashMap<&str, Vec<f64>> = HashMap::new();
+        for (entry, weight) in entries.iter().zip(weights) {
+            groups.entry(entry.category()).or_default().push(*weight);
+        }
+        let totals: HashMap<&str, f64> = groups
+            .into_iter()
+            .map(|(category, mut weights)| {
+                weights.sort_by(f64::total_cmp);
+                (category, weights.iter().sum())
+            })
+            .collect();
+
+        let probs: Vec<f64> = entries
+            .iter()
+            .zip(weights)
+            .map(|(entry, weight)| weight / totals[entry.category()])
+            .collect();
+
+        let mut lexemes: HashMap<LexemeId, (f64, f64)> = HashMap::new();
+        for ((id, prob), uniform) in ids
+            .iter()
+            .zip(probs.iter())
+            .zip(uniform_probabilities(entries))
+        {
+            let (weighted, _) = lexemes.entry(*id).or_insert((0.0, uniform));
+            *weighted += prob;
+        }
+
+        let to_log_prob =
+            |p: f64| LogProb::from_raw_prob(p).map_err(|e| MGError::new_err(e.to_string()));
+        Ok(Weights {
+            given: weights.to_vec(),
+            entries: probs
+                .into_iter()
+                .map(to_log_prob)
+                .collect::<PyResult<_>>()?,
+            lexemes: lexemes
+                .into_iter()
+                .map(|(id, (weighted, uniform))| {
+                    Ok((id, (to_log_prob(weighted)?, to_log_prob(uniform)?)))
+                })
+                .collect::<PyResult<_>>()?,
+        })
+    }
+
+    ///The probability of each entry.
+    pub(crate) fn entries(&self) -> &[LogProb<f64>] {
+        &self.entries
+    }
+
+    ///Adjusts the probability of a derivation found by the parser to use the weights.
+    pub(crate) fn reweight(&self, prob: LogProb<f64>, rules: &RulePool) -> PyResult<LogProb<f64>> {
+        let log_ratio: f64 = rules
+            .used_lemmas()
+            .filter_map(|id| self.lexemes.get(&id))
+            .map(|(weighted, uniform)| weighted.into_inner() - uniform.into_inner())
+            .sum();
+        let log_prob = prob.into_inner() + log_ratio;
+        let log_prob = if (0.0..=ROUNDING).contains(&log_prob) {
+            0.0
+        } else {
+            log_prob
+        };
+        LogProb::new(log_prob).map_err(|_| {
+            MGError::new_err(format!(
+                "A derivation has a weighted log probability of {log_prob}, which is above 0"
+            ))
+        })
+    }
+
+    ///The most that the weights raise the log probability of a single use of a lexeme.
+    fn max_gain(&self) -> f64 {
+        self.lexemes
+            .values()
+            .map(|(weighted, uniform)| weighted.into_inner() - uniform.into_inner())
+            .fold(0.0, f64::max)
+    }
+}
+
+///How to search for the derivations of a lexicon.
+///
+///The parser prunes its search with the probabilities it gives derivations, which do not take the
+///weights of a lexicon into account. For a weighted lexicon, the parser's bound on log probability
+///is loosened by as much as the weights could raise the log probability of a derivation, and the
+///derivations it finds are then held to the bound by their weighted probability.
+pub(crate) struct Search {
+    ///The configuration of the parser.
+    pub(crate) config: ParsingConfig,
+    ///The bound on the weighted log probability of derivations, if the parser does not enforce it.
+    min_log_prob: Option<LogProb<f64>>,
+}
+
+impl From<ParsingConfig> for Search {
+    fn from(config: ParsingConfig) -> Self {
+        Search {
+            config,
+            min_log_prob: None,
+        }
+    }
+}
+
+impl PyLexicon {
+    ///The weight each entry was given, before normalisation, from which a copy of the lexicon
+    ///gets exactly the same probabilities.
+    pub(crate) fn given_weights(&self) -> Option<HashMap<String, f64>> {
+        self.weights.as_ref().map(|weights| {
+            self.entries
+                .iter()
+                .map(|entry| entry.to_string())
+                .zip(weights.given.iter().copied())
+                .collect()
+        })
+    }
+
+    ///The search for derivations with the parameters of the parser given to a method. Beam search
+    ///is not supported for weighted lexicons, as the parser would rank the beams without the
+    ///weights, and their searches need a limit on steps to be finite.
+    pub(crate) fn search(
+        &self,
+        min_log_prob: Option<f64>,
+        move_prob: f64,
+        max_steps: Option<usize>,
+        n_beams: Option<usize>,
+    ) -> PyResult<Search> {
+        let Some(weights) = &self.weights else {
+            return Ok(get_config(min_log_prob, move_prob, max_steps, n_beams)?.into());
+        };
+        if n_beams.is_some() {
+            return Err(MGError::new_err(
+                "n_beams is not supported for weighted lexicons, as beams are ranked without weights",
+            ));
+        }
+
+        //The most probable derivations of a weighted lexicon are only known once every derivation
+        //has been found, which never happens for a recursive grammar without a limit on steps.
+        let Some(max_steps) = max_steps else {
+            return Err(MGError::new_err(
+                "max_steps must be set for weighted lexicons, as every derivation has to be found",
+            ));
+        };
+
+        //Each use of a lexeme takes at least one step of a derivation, so the weights raise the
+        //log probability of a derivation by at most `max_gain` per step.
+        let gain = weights.max_gain();
+        let parser_min_log_prob = min_log_prob.map(|x| x - gain * max_steps as f64);
+        Ok(Search {
+            config: get_config(parser_min_log_prob, move_prob, Some(max_steps), None)?,
+            min_log_prob: min_log_prob
+                .map(|x| LogProb::new(x).map_err(|e| MGError::new_err(e.to_string())))
+                .transpose()?,
+        })
+    }
+
+    ///The probability of a derivation found by the parser, taking the weights of the lexicon into
+    ///account.
+    pub(crate) fn reweight(&self, prob: LogProb<f64>, rules: &RulePool) -> PyResult<LogProb<f64>> {
+        match &self.weights {
+            Some(weights) => weights.reweight(prob, rules),
+            None => Ok(prob),
+        }
+    }
+
+    ///The weighted probability of a derivation found in `search`, or `None` if it is below the
+    ///search's bound on log probability.
+    pub(crate) fn reweight_within(
+        &self,
+        search: &Search,
+        prob: LogProb<f64>,
+        rules: &RulePool,
+    ) -> PyResult<Option<LogProb<f64>>> {
+        let prob = self.reweight(prob, rules)?;
+        Ok(match search.min_log_prob {
+            Some(min_log_prob) if prob < min_log_prob => None,
+            _ => Some(prob),
+        })
+    }
+
+    ///The words which can follow `prefix` in a string of `category`.
+    ///
+    ///If the lexicon is weighted and the search has a bound on log probability, the parser cannot
+    ///tell which continuations only come from derivations below the bound, so the strings of the
+    ///lexicon are generated and their weighted probabilities checked instead.
+    pub(crate) fn continuations_of(
+        &self,
+        category: &str,
+        prefix: &[PhonContent<&str>],
+        search: &Search,
+    ) -> PyResult<HashSet<Continuation<String>>> {
+        let owned = |continuation: Continuation<&str>| match continuation {
+            Continuation::Word(x) => Continuation::Word(x.to_string()),
+            Continuation::AffixedWord(items) => {
+                Continuation::AffixedWord(items.into_iter().map(|x| x.to_string()).collect())
+            }
+            Continuation::EndOfSentence => Continuation::EndOfSentence,
+        };
+
+        let lexicon = self.lexicon.lexicon();
+        if self.weights.is_none() || search.min_log_prob.is_none() {
+            return Ok(lexicon
+                .valid_continuations(&category, prefix, &search.config)
+                .map_err(|e| MGError::new_err(e.to_string()))?
+                .into_iter()
+                .map(owned)
+                .collect());
+        }
+
+        let mut continuations = HashSet::new();
+        for (prob, string, rules) in lexicon
+            .generate(category, &search.config)
+            .map_err(|e| MGError::new_err(e.to_string()))?
+        {
+            if !string.starts_with(prefix) || self.reweight_within(search, prob, &rules)?.is_none()
+            {
+                continue;
+            }
+            continuations.insert(owned(match string.get(prefix.len()) {
+                None => Continuation::EndOfSentence,
+                Some(PhonContent::Normal(x)) => Continuation::Word(*x),
+                Some(PhonContent::Affixed(items)) => Continuation::AffixedWord(items.clone()),
+            }));
+        }
+        Ok(continuations)
+    }
+}
+
+///The maximisation step of EM: sets the weight of each entry to the expected number of times its
+///lexeme is used (plus `smoothing`). Groups of entries which are never used keep their weights.
+fn maximise(
+    entries: &[Entry],
+    ids: &[LexemeId],
+    counts: &HashMap<LexemeId, f64>,
+    smoothing: f64,
+    old: &[f64],
+) -> Vec<f64> {
+    let mut n_entries: HashMap<LexemeId, usize> = HashMap::new();
+    for id in ids {
+        *n_entries.entry(*id).or_default() += 1;
+    }
+
+    let new: Vec<f64> = ids
+        .iter()
+        .map(|id| counts.get(id).copied().unwrap_or(0.0) / n_entries[id] as f64 + smoothing)
+        .collect();
+
+    let mut totals: HashMap<&str, f64> = HashMap::new();
+    for (entry, weight) in entries.iter().zip(new.iter()) {
+        *totals.entry(entry.category()).or_default() += weight;
+    }
+
+    entries
+        .iter()
+        .zip(new.iter().zip(old))
+        .map(|(entry, (new, old))| {
+            if totals[entry.category()] > 0.0 {
+                *new
+            } else {
+                *old
+            }
+        })
+        .collect()
+}
+
+#[pymethods]
+impl PyLexicon {
+    #[allow(clippy::too_many_arguments)]
+    #[pyo3(signature = (corpus, category, iterations=10, smoothing=0.0, min_log_prob=None, move_prob=0.5, max_steps=64, n_beams=None))]
+    ///Fits the weights of the lexical entries to a corpus by maximum likelihood, using expectation
+    ///maximisation over every parse of each string.
+    ///
+    ///Each string is parsed once; each iteration then computes the expected number of times each
+    ///lexical entry is used given the current weights, and sets the weights proportionally. If the
+    ///lexicon is weighted, its weights are the starting point, otherwise every entry starts with the
+    ///same weight.
+    ///
+    ///Parameters
+    ///----------
+    ///corpus : list[str]
+    ///    The strings to fit, delimited as in :meth:`python_mg.Lexicon.parse`.
+    ///category : str
+    ///    The syntactic category of the strings of the corpus.
+    ///iterations : int, optional
+    ///    The number of iterations of EM.
+    ///    Default is 10.
+    ///smoothing : float, optional
+    ///    A pseudo-count added to the expected count of every entry. Entries with a weight of 0 are
+    ///    removed from the returned lexicon.
+    ///    Default is 0.0.
+    ///min_log_prob : float or None, optional
+    ///    Minimum log probability threshold for the parser to consider
+    ///    Default is None.
+    ///move_prob : float, optional
+    ///    Probability of preferring a move over a merge when parsing.
+    ///    Default is 0.5
+    ///max_steps : int or None, optional
+    ///    Maximum number of derivation steps. If None, will not be limited.
+    ///    Default is 64.
+    ///n_beams : int or None, optional
+    ///    Number of beams to maintain while parsing. If None, will not be limited.
+    ///    Default is None.
+    ///
+    ///Returns
+    ///-------
+    ///tuple[Lexicon, list[float]]
+    ///    The weighted lexicon and the log-likelihood of the corpus at the start of each iteration.
+    ///
+    ///Raises
+    ///------
+    ///MGError
+    ///    If a string of the corpus cannot be parsed.
+    fn fit_weights(
+        &self,
+        corpus: Vec<String>,
+        category: &str,
+        iterations: usize,
+        smoothing: f64,
+        min_log_prob: Option<f64>,
+        move_prob: f64,
+        max_steps: Option<usize>,
+        n_beams: Option<usize>,
+    ) -> PyResult<(PyLexicon, Vec<f64>)> {
+        if !(smoothing.is_finite() && smoothing >= 0.0) {
+            return Err(MGError::new_err(
+                "smoothing must be finite and non-negative",
+            ));
+        }
+
+        let config = get_config(min_log_prob, move_prob, max_steps, n_beams)?;
+        let ids = self
+            .entries
+            .iter()
+            .map(|x| self.lexeme_id(x))
+            .collect::<PyResult<Vec<_>>>()?;
+
+        let derivations = corpus
+            .iter()
+            .map(|s| {
+                let parses: Vec<(LogProb<f64>, RulePool)> = self
+                    .lexicon
+                    .lexicon()
+                    .parse(&map_string(s), category, &config)
+                    .map_err(|e| MGError::new_err(e.to_string()))?
+                    .map(|(prob, _, rules)| (prob, rules))
+                    .collect();
+                if parses.is_empty() {
+                    Err(MGError::new_err(format!("'{s}' could not be parsed")))
+                } else {
+                    Ok(parses)
+                }
+            })
+            .collect::<PyResult<Vec<_>>>()?;
+
+        let mut weights: Vec<f64> = match &self.weights {
+            Some(weights) => weights
+                .entries()
+                .iter()
+                .map(|x| x.into_inner().exp())
+                .collect(),
+            None => vec![1.0; self.entries.len()],
+        };
+
+        let mut log_likelihoods = vec![];
+        for _ in 0..iterations {
+            let model = Weights::new(&self.entries, &weights, &ids)?;
+            let mut counts: HashMap<LexemeId, f64> = HashMap::new();
+            let mut log_likelihood = 0.0;
+            for parses in &derivations {
+                let log_probs: Vec<f64> = parses
+                    .iter()
+                    .map(|(prob, rules)| Ok(model.reweight(*prob, rules)?.into_inner()))
+                    .collect::<PyResult<_>>()?;
+                let total = log_sum_exp(log_probs.iter().copied());
+                log_likelihood += total;
+                if total == f64::NEG_INFINITY {
+                    continue;
+                }
+                for ((_, rules), log_prob) in parses.iter().zip(log_probs) {
+                    let posterior = (log_prob - total).exp();
+                    for id in rules.used_lemmas() {
+                        *counts.entry(id).or_default() += posterior;
+                    }
+                }
+            }
+            log_likelihoods.push(log_likelihood);
+            weights = maximise(&self.entries, &ids, &counts, smoothing, &weights);
+        }
+
+        let grammar = self.entries.iter().join("\n");
+        let weights = self
+            .entries
+            .iter()
+            .zip(weights)
+            .map(|(entry, weight)| (entry.to_string(), weight))
+            .collect();
+        let lexicon = PyLexicon::new(
+            grammar,
+            Some(self.is_semantic()),
+            Some(weights),
+            Some(self.word_id.ids().clone()),
+            Some(self.metadata.clone()),
+        )?;
+        Ok((lexicon, log_likelihoods))
+    }
+}